@@ -0,0 +1,41 @@
+//! Verifies a chain of session segment proofs and commits one set of public values for the
+//! whole session, see `turbo_program::aggregation` for the stdin format.
+
+// These two lines are necessary for the program to properly compile.
+//
+// Under the hood, we wrap your main function with some extra code so that it behaves properly
+// inside the zkVM.
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use alloy_sol_types::SolValue;
+use sha2::{Digest, Sha256};
+use turbo_program::{
+    aggregation::aggregate_segments,
+    transcript::{TurboAggregatedPublicValues, TurboPublicValues},
+};
+
+pub fn main() {
+    let segment_vkey = sp1_zkvm::io::read::<[u32; 8]>();
+    let segment_public_values = sp1_zkvm::io::read::<Vec<Vec<u8>>>();
+
+    // Verify every segment proof against its public values, the proofs themselves are
+    // provided to the prover with `SP1Stdin::write_proof`
+    let mut segments = Vec::new();
+    for public_values in &segment_public_values {
+        let digest: [u8; 32] = Sha256::digest(public_values).into();
+        sp1_zkvm::lib::verify::verify_sp1_proof(&segment_vkey, &digest);
+        segments.push(
+            TurboPublicValues::abi_decode(public_values).expect("Invalid segment public values"),
+        );
+    }
+
+    let session = aggregate_segments(&segments).unwrap_or_else(|e| panic!("{}", e));
+
+    let public_values = TurboAggregatedPublicValues {
+        segmentVkey: segment_vkey,
+        segmentCount: segments.len() as u32,
+        session,
+    };
+    sp1_zkvm::io::commit_slice(&public_values.abi_encode());
+}
@@ -0,0 +1,25 @@
+//! Hashes every input with `FnvHasher` and commits the limbs and shift of each digest, so the
+//! fixed vectors of `turbo-program/tests/vectors/fnv.rs` can be checked against the zkVM arithmetic.
+
+// These two lines are necessary for the program to properly compile.
+//
+// Under the hood, we wrap your main function with some extra code so that it behaves properly
+// inside the zkVM.
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use turbo_program::crypto::fnv::FnvHasher;
+
+pub fn main() {
+    let inputs = sp1_zkvm::io::read::<Vec<Vec<u8>>>();
+
+    let digests = inputs
+        .iter()
+        .map(|input| {
+            let mut hasher = FnvHasher::new();
+            hasher.next(input);
+            (hasher.get(), hasher.shift() as u32)
+        })
+        .collect::<Vec<_>>();
+    sp1_zkvm::io::commit(&digests);
+}
@@ -1,5 +1,5 @@
 use serde_json::Value;
-use turbo_program::traits::TurboActionSerialization;
+use turbo_program::{error::TurboError, traits::TurboActionSerialization};
 
 #[derive(Debug)]
 pub enum GameAction {
@@ -7,33 +7,48 @@ pub enum GameAction {
 }
 
 impl TurboActionSerialization for GameAction {
-    fn deserialize(action: &[u8]) -> Result<(Self, &[u8]), &'static str> {
-        let action_type = action[0];
-        Ok((
-            GameAction::MoveAndRandomTileAction(action_type),
-            &action[1..],
-        ))
+    fn deserialize(action: &[u8]) -> Result<(Self, &[u8]), TurboError> {
+        let (action_type, rest) = action
+            .split_first()
+            .ok_or(TurboError::decode("Unexpected end of action"))?;
+        Ok((GameAction::MoveAndRandomTileAction(*action_type), rest))
     }
 
-    fn serialize_json(json_str: &str) -> Result<Vec<u8>, &'static str> {
-        let action: Value = serde_json::from_str(json_str).map_err(|_| "Invalid JSON")?;
+    fn serialize(&self) -> Vec<u8> {
+        match self {
+            GameAction::MoveAndRandomTileAction(direction) => vec![*direction],
+        }
+    }
+
+    fn serialize_json(json_str: &str) -> Result<Vec<u8>, TurboError> {
+        let action: Value = serde_json::from_str(json_str)
+            .map_err(|_| TurboError::InvalidAction("Invalid JSON"))?;
         let mut result = Vec::new();
 
         if let Some(action_u8) = action.as_u64().map(|n| n as u8) {
             result.push(action_u8);
         } else {
-            let action_type = action["action"].as_str().ok_or("Missing action field")?;
-            let data = action["data"].as_array().ok_or("Missing data field")?;
+            let action_type = action["action"]
+                .as_str()
+                .ok_or(TurboError::InvalidAction("Missing action field"))?;
+            let data = action["data"]
+                .as_array()
+                .ok_or(TurboError::InvalidAction("Missing data field"))?;
 
             match action_type {
                 "MoveAndRandomTileAction" => {
                     if data.len() != 1 {
-                        return Err("Invalid data length for MoveAndRandomTileAction");
+                        return Err(TurboError::InvalidAction(
+                            "Invalid data length for MoveAndRandomTileAction",
+                        ));
                     }
-                    let direction = data[0].as_u64().ok_or("Invalid direction")? as u8;
+                    let direction = data[0]
+                        .as_u64()
+                        .ok_or(TurboError::InvalidAction("Invalid direction"))?
+                        as u8;
                     result.push(direction);
                 }
-                _ => return Err("Invalid action type"),
+                _ => return Err(TurboError::InvalidAction("Invalid action type")),
             }
         }
 
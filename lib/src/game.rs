@@ -0,0 +1,44 @@
+use turbo_program::{
+    context::TurboActionContext,
+    crypto::fnv::FnvHasher,
+    error::GameError,
+    game::{NoSystemAction, TurboGame},
+    metadata::PlayerMetadata,
+};
+
+use crate::{
+    action::GameAction,
+    reducer::{is_game_over, reducer},
+    state::{GamePrivateState, GamePublicState},
+};
+
+pub struct Game2048;
+
+impl TurboGame for Game2048 {
+    type PublicState = GamePublicState;
+    type Output = GamePublicState;
+    type PlayerView = GamePublicState;
+    type PrivateState = GamePrivateState;
+    type Action = GameAction;
+    type SystemAction = NoSystemAction;
+    type Config = ();
+    type ActionHasher = FnvHasher;
+
+    fn init(_config: &(), _players: &[PlayerMetadata]) -> (GamePublicState, GamePrivateState) {
+        (GamePublicState::default(), GamePrivateState::default())
+    }
+
+    fn reduce(
+        public_state: &mut GamePublicState,
+        private_state: &mut GamePrivateState,
+        action: &GameAction,
+        context: &mut TurboActionContext,
+    ) -> Result<(), GameError> {
+        reducer(public_state, private_state, action, context)
+    }
+
+    fn is_terminal(public_state: &GamePublicState, private_state: &GamePrivateState) -> bool {
+        // The board starts empty, no move changes it before the first tile
+        private_state.moves > 0 && is_game_over(&public_state.board)
+    }
+}
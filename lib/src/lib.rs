@@ -1,4 +1,5 @@
 pub mod action;
+pub mod game;
 pub mod hook;
 pub mod reducer;
 pub mod state;
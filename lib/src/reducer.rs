@@ -1,5 +1,5 @@
 use serde_json::json;
-use turbo_program::context::TurboActionContext;
+use turbo_program::{context::TurboActionContext, error::GameError};
 
 use crate::{action::GameAction, state::GamePrivateState, state::GamePublicState};
 
@@ -125,27 +125,9 @@ pub fn move_board(board: &[[u32; 4]; 4], direction: u8) -> [[u32; 4]; 4] {
     result
 }
 
-/// Helper function to check if a line can be merged/moved
-fn can_merge_line(line: &[u32; 4]) -> bool {
-    // Check for possible merges
-    for i in 0..3 {
-        if line[i] != 0 && line[i] == line[i + 1] {
-            return true;
-        }
-    }
-
-    // Check for possible moves (gaps between numbers)
-    let mut found_zero = false;
-    for i in 0..4 {
-        if line[i] == 0 {
-            found_zero = true;
-        } else if found_zero {
-            // Found a non-zero number after a zero
-            return true;
-        }
-    }
-
-    false
+/// The game is over once no move changes the board.
+pub fn is_game_over(board: &[[u32; 4]; 4]) -> bool {
+    (0..4).all(|direction| move_board(board, direction) == *board)
 }
 
 pub fn reducer(
@@ -153,7 +135,7 @@ pub fn reducer(
     private_state: &mut GamePrivateState,
     action: &GameAction,
     context: &mut TurboActionContext,
-) {
+) -> Result<(), GameError> {
     match action {
         // GameAction::MoveAction(direction) => {
         //     public_state.board = move_board(&public_state.board, *direction);
@@ -167,11 +149,15 @@ pub fn reducer(
         //     }
         // }
         GameAction::MoveAndRandomTileAction(direction) => {
+            if *direction > 3 {
+                return Err(GameError::new("Invalid direction"));
+            }
+
             *context.client_response() = None;
 
             // A fix for initial state
             if private_state.moves == 0 {
-                let rand = context.rand_u32() % 16;
+                let rand = context.rand_range(0..16);
                 let (r, c) = (rand as usize / 4, rand as usize % 4);
                 public_state.board[r][c] = 2;
                 private_state.moves += 1;
@@ -183,7 +169,7 @@ pub fn reducer(
                     }));
                 }
 
-                return;
+                return Ok(());
             }
 
             let new_board = move_board(&public_state.board, *direction);
@@ -191,13 +177,13 @@ pub fn reducer(
 
             let mut empty_positions = Vec::new();
             let mut is_moved = false;
-            for row in 0..4 {
-                for col in 0..4 {
-                    if new_board[row][col] == 0 {
+            for (row, (old_row, new_row)) in public_state.board.iter().zip(&new_board).enumerate() {
+                for (col, (old, new)) in old_row.iter().zip(new_row).enumerate() {
+                    if *new == 0 {
                         empty_positions.push((row, col));
                     }
 
-                    if public_state.board[row][col] != new_board[row][col] {
+                    if old != new {
                         is_moved = true;
                     }
                 }
@@ -207,8 +193,7 @@ pub fn reducer(
                 public_state.board = new_board;
 
                 if !empty_positions.is_empty() {
-                    let rand = context.rand_u32();
-                    let (r, c) = empty_positions[rand as usize % empty_positions.len()];
+                    let (r, c) = *context.choose(&empty_positions).unwrap();
                     public_state.board[r][c] = 2;
 
                     #[cfg(not(target_os = "zkvm"))]
@@ -221,4 +206,6 @@ pub fn reducer(
             }
         }
     }
+
+    Ok(())
 }
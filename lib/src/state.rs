@@ -8,7 +8,7 @@ sol! {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct GamePrivateState {
     pub moves: u32,
 }
@@ -8,9 +8,9 @@
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 
-use game_lib::reducer::reducer;
+use game_lib::game::Game2048;
 use turbo_program::program::turbo_program;
 
 pub fn main() {
-    turbo_program(reducer);
+    turbo_program::<Game2048>();
 }
@@ -1,5 +1,7 @@
 use sp1_build::build_program_with_args;
 
 fn main() {
-    build_program_with_args("../program", Default::default())
+    build_program_with_args("../program", Default::default());
+    build_program_with_args("../aggregation-program", Default::default());
+    build_program_with_args("../fnv-program", Default::default());
 }
@@ -12,7 +12,7 @@
 
 use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
-use sp1_sdk::{include_elf, HashableKey, ProverClient, SP1Stdin, SP1VerifyingKey};
+use sp1_sdk::{include_elf, HashableKey, ProverClient, SP1Stdin};
 use std::num::ParseIntError;
 use std::path::PathBuf;
 use std::str::FromStr;
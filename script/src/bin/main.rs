@@ -1,17 +1,20 @@
-use game_lib::reducer::reducer;
+use game_lib::game::Game2048;
 use sp1_sdk::include_elf;
-use turbo_sp1::server::turbo_sp1_routes;
+use turbo_sp1::server::turbo_sp1_routes_with_aggregation;
 
 /// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
 pub const GAME_ELF: &[u8] = include_elf!("game-program");
 
+/// The ELF of the program aggregating session segment proofs.
+pub const AGGREGATION_ELF: &[u8] = include_elf!("aggregation-program");
+
 #[tokio::main]
 async fn main() {
     // Setup the logger.
     sp1_sdk::utils::setup_logger();
     dotenv::dotenv().ok();
 
-    let routes = turbo_sp1_routes(GAME_ELF, reducer, 4);
+    let routes = turbo_sp1_routes_with_aggregation::<Game2048>(GAME_ELF, Some(AGGREGATION_ELF), 4);
 
     // Get port from environment variable or use default 3030
     let port = std::env::var("PORT")
@@ -12,7 +12,7 @@
 
 use alloy_sol_types::SolType;
 use clap::Parser;
-use game_lib::state::GamePublicState;
+use game_lib::{game::Game2048, state::GamePublicState};
 use rand::thread_rng;
 use sp1_sdk::{include_elf, ProverClient, SP1Stdin};
 use std::num::ParseIntError;
@@ -20,7 +20,11 @@ use std::str::FromStr;
 use substrate_bn::*;
 use turbo_sp1::{
     crypto::bn_serialize::bn254_export_affine_g1_memcpy,
+    framing::{encode_actions, stamp_action, ActionSender},
+    game::TurboGame,
     metadata::{PlayerMetadata, ServerMetadata},
+    traits::TurboActionSerialization,
+    transcript::TurboPublicValues,
 };
 
 /// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
@@ -58,6 +62,23 @@ struct Args {
     actions: VecString,
 }
 
+/// Stamp every action with a server timestamp, one second apart, as `TurboSession` would.
+fn stamp_actions(actions: &[u8]) -> Vec<u8> {
+    let mut stamped = Vec::new();
+    let mut remaining_actions = actions;
+    let mut timestamp = 1_700_000_000_000;
+    while !remaining_actions.is_empty() {
+        let (_, action_bytes) = ActionSender::decode(remaining_actions).expect("Invalid sender");
+        let (_, next_actions) =
+            <Game2048 as TurboGame>::Action::deserialize(action_bytes).expect("Invalid action");
+        let action_raw = &remaining_actions[..remaining_actions.len() - next_actions.len()];
+        stamped.extend(stamp_action(action_raw, timestamp).expect("Invalid action"));
+        remaining_actions = next_actions;
+        timestamp += 1_000;
+    }
+    stamped
+}
+
 fn main() {
     // Setup the logger.
     sp1_sdk::utils::setup_logger();
@@ -82,20 +103,23 @@ fn main() {
     let server_metadata = ServerMetadata {
         random_seed: bn254_export_affine_g1_memcpy(&server_random_seed),
     };
-    let player_metadata: PlayerMetadata = PlayerMetadata {
-        random_seed: bn254_export_affine_g1_memcpy(&player_random_seed),
-    };
+    let player_metadata = PlayerMetadata::new(bn254_export_affine_g1_memcpy(&player_random_seed));
     let player_metadatas = vec![player_metadata];
 
     // Setup the prover client.
     let client = ProverClient::from_env();
 
-    // Setup the inputs.
+    // Setup the inputs, in the order `turbo_program` reads them: server metadata, game config,
+    // initial players, prior checkpoint, actions, view secret and no trace.
     let mut stdin = SP1Stdin::new();
     stdin.write(&server_metadata);
+    stdin.write(&());
     stdin.write(&player_metadatas);
+    stdin.write(&None::<Vec<u8>>);
     let repeated_actions = args.actions.0.repeat(100);
-    stdin.write(&repeated_actions);
+    stdin.write(&encode_actions(&stamp_actions(&repeated_actions)));
+    stdin.write(&rand::random::<[u8; 32]>());
+    stdin.write(&false);
 
     //println!("actions: {:?}", repeated_actions);
 
@@ -105,9 +129,11 @@ fn main() {
         println!("Program executed successfully.");
 
         // Read the output.
-        let decoded: GamePublicState = GamePublicState::abi_decode(output.as_slice()).unwrap();
+        let public_values = TurboPublicValues::abi_decode(output.as_slice()).unwrap();
+        let decoded: GamePublicState = GamePublicState::abi_decode(&public_values.state).unwrap();
         let GamePublicState { board } = decoded;
         println!("board: {:?}", board);
+        println!("action hashes: {:?}", public_values.actionHashes);
         // println!("num: {:?}", num);
         // println!("hash: {:?}", hash);
 
@@ -141,10 +167,11 @@ fn main() {
         println!("Successfully verified proof in: {:?}", verify_duration);
 
         // Read the output.
-        let decoded: GamePublicState =
-            GamePublicState::abi_decode(proof.public_values.as_slice()).unwrap();
+        let public_values = TurboPublicValues::abi_decode(proof.public_values.as_slice()).unwrap();
+        let decoded: GamePublicState = GamePublicState::abi_decode(&public_values.state).unwrap();
         let GamePublicState { board } = decoded;
         println!("board: {:?}", board);
+        println!("action hashes: {:?}", public_values.actionHashes);
         // println!("num: {:?}", num);
         // println!("hash: {:?}", hash);
 
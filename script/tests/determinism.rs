@@ -0,0 +1,79 @@
+use ed25519_dalek::{Signer as _, SigningKey};
+use game_lib::game::Game2048;
+use sp1_sdk::{include_elf, ProverClient};
+use substrate_bn::{AffineG1, Fr, Group, G1};
+use turbo_sp1::{
+    crypto::{
+        bn_serialize::bn254_export_affine_g1_memcpy,
+        signature::{action_signing_message, PlayerPublicKey},
+    },
+    determinism::{assert_deterministic, TurboTranscript},
+    metadata::{PlayerMetadata, ServerMetadata},
+};
+
+const GAME_ELF: &[u8] = include_elf!("game-program");
+
+fn seed(scalar: &str) -> [u32; 16] {
+    let point = AffineG1::from_jacobian(G1::one() * Fr::from_str(scalar).unwrap()).unwrap();
+    bn254_export_affine_g1_memcpy(&point)
+}
+
+#[test]
+fn game_2048_is_deterministic() {
+    // Directions above 3 are rejected, they must be skipped the same way on both paths
+    let directions = [0, 1, 2, 3, 9, 0, 2, 1, 3, 3, 2, 0, 1, 7, 2, 2, 1, 0];
+    let transcript = TurboTranscript::<Game2048> {
+        config: (),
+        server_metadata: ServerMetadata {
+            random_seed: seed("12345"),
+        },
+        player_metadata: vec![
+            PlayerMetadata::new(seed("678")),
+            PlayerMetadata::new(seed("91011")),
+        ],
+        actions: directions
+            .iter()
+            .enumerate()
+            .map(|(i, direction)| {
+                (
+                    1_700_000_000_000 + i as u64 * 250,
+                    vec![(i % 2) as u8, *direction],
+                )
+            })
+            .collect(),
+    };
+
+    assert_deterministic(&ProverClient::from_env(), GAME_ELF, &transcript);
+}
+
+#[test]
+fn rejected_signed_actions_are_deterministic() {
+    let key = SigningKey::from_bytes(&[7; 32]);
+    let server_metadata = ServerMetadata {
+        random_seed: seed("12345"),
+    };
+    let session_id = server_metadata.seed_commitment();
+
+    // The rejected direction 9 does not use up sequence 1, the next action signs it again
+    let signed = [(0, 2), (1, 9), (1, 3), (2, 0)].map(|(sequence, direction)| {
+        let message = action_signing_message(&session_id, sequence, &[direction]);
+        let mut action = vec![0, direction];
+        action.extend(key.sign(&message).to_bytes());
+        action
+    });
+    let transcript = TurboTranscript::<Game2048> {
+        config: (),
+        server_metadata,
+        player_metadata: vec![PlayerMetadata {
+            random_seed: seed("678"),
+            public_key: Some(PlayerPublicKey::Ed25519(key.verifying_key().to_bytes())),
+        }],
+        actions: signed
+            .into_iter()
+            .enumerate()
+            .map(|(i, action)| (1_700_000_000_000 + i as u64 * 250, action))
+            .collect(),
+    };
+
+    assert_deterministic(&ProverClient::from_env(), GAME_ELF, &transcript);
+}
@@ -0,0 +1,60 @@
+#[path = "../../turbo-program/tests/vectors/fnv.rs"]
+mod vectors;
+
+use alloy_sol_types::SolType;
+use game_lib::game::Game2048;
+use sp1_sdk::{include_elf, ProverClient, SP1Stdin};
+use turbo_sp1::{session::TurboSession, transcript::TurboPublicValues};
+use vectors::fnv_vectors;
+
+const GAME_ELF: &[u8] = include_elf!("game-program");
+const FNV_ELF: &[u8] = include_elf!("fnv-program");
+
+/// The fixed vectors checked on the host must come out of the zkVM arithmetic bit for bit.
+#[test]
+fn fnv_vectors_match_zkvm() {
+    let vectors = fnv_vectors();
+    let mut stdin = SP1Stdin::new();
+    stdin.write(
+        &vectors
+            .iter()
+            .map(|(_, input, _, _)| input.clone())
+            .collect::<Vec<_>>(),
+    );
+
+    let client = ProverClient::from_env();
+    let (mut output, _) = client.execute(FNV_ELF, &stdin).run().unwrap();
+    let digests = output.read::<Vec<([u32; 8], u32)>>();
+
+    assert_eq!(digests.len(), vectors.len());
+    for ((name, _, digest, shift), zkvm) in vectors.iter().zip(digests) {
+        assert_eq!(zkvm, (*digest, *shift as u32), "{}", name);
+    }
+}
+
+/// Action hashes computed by the session (host `U256` arithmetic) must match the ones the
+/// program commits (`sys_bigint` in the zkVM).
+#[test]
+fn fnv_action_hashes_match_zkvm() {
+    let mut session = TurboSession::<Game2048>::new(());
+    let players = [
+        session.join_random().unwrap() as u8,
+        session.join_random().unwrap() as u8,
+    ];
+
+    // The 64 seed bytes plus a few actions per player cover full and partial words
+    for (i, direction) in [0, 1, 2, 3, 0, 2, 1, 3, 2].into_iter().enumerate() {
+        session
+            .dispatch(&[players[i % players.len()], direction])
+            .unwrap();
+    }
+
+    let client = ProverClient::from_env();
+    let (output, _) = client
+        .execute(GAME_ELF, &session.sp1_stdin())
+        .run()
+        .unwrap();
+    let public_values = TurboPublicValues::abi_decode(output.as_slice()).unwrap();
+
+    assert_eq!(public_values.actionHashes, session.action_hashes());
+}
@@ -0,0 +1,228 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitInt, LitStr, Type, Variant};
+
+/// Derive `TurboActionSerialization` for an enum of game actions.
+///
+/// Each variant is encoded as a tag byte followed by its fields (see `TurboActionField`).
+/// Tags default to the variant index and can be pinned with `#[turbo_action(tag = N)]`,
+/// the JSON action name defaults to the variant name and can be changed with
+/// `#[turbo_action(rename = "...")]`.
+///
+/// ```ignore
+/// #[derive(TurboAction)]
+/// enum GameAction {
+///     Move(u8),
+///     #[turbo_action(tag = 5)]
+///     Attack { x: u32, y: u32 },
+/// }
+/// ```
+#[proc_macro_derive(TurboAction, attributes(turbo_action))]
+pub fn derive_turbo_action(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct ActionVariant {
+    ident: Ident,
+    tag: u8,
+    name: String,
+    bindings: Vec<Ident>,
+    types: Vec<Type>,
+    pattern: TokenStream2,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "TurboAction can only be derived for enums",
+            ))
+        }
+    };
+
+    if data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "TurboAction requires at least one action",
+        ));
+    }
+
+    let mut variants = Vec::new();
+    let mut next_tag: u16 = 0;
+    for variant in &data.variants {
+        let action_variant = parse_variant(variant, next_tag)?;
+        if let Some(other) = variants
+            .iter()
+            .find(|v: &&ActionVariant| v.tag == action_variant.tag)
+        {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                format!(
+                    "duplicate action tag {} (also used by {})",
+                    other.tag, other.ident
+                ),
+            ));
+        }
+        next_tag = action_variant.tag as u16 + 1;
+        variants.push(action_variant);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let field_trait = quote!(::turbo_program::traits::TurboActionField);
+    let error = quote!(::turbo_program::error::TurboError);
+
+    let deserialize_arms = variants.iter().map(|v| {
+        let tag = v.tag;
+        let pattern = &v.pattern;
+        let decodes = v.bindings.iter().zip(&v.types).map(|(binding, ty)| {
+            quote! {
+                let (#binding, rest) = <#ty as #field_trait>::decode_field(rest)
+                    .map_err(|e| e.at_offset(action.len() - rest.len()))?;
+            }
+        });
+        quote! {
+            #tag => {
+                #(#decodes)*
+                Ok((Self::#pattern, rest))
+            }
+        }
+    });
+
+    let serialize_arms = variants.iter().map(|v| {
+        let tag = v.tag;
+        let pattern = &v.pattern;
+        let encodes = v.bindings.iter().zip(&v.types).map(|(binding, ty)| {
+            quote! {
+                <#ty as #field_trait>::encode_field(#binding, &mut out);
+            }
+        });
+        quote! {
+            Self::#pattern => {
+                out.push(#tag);
+                #(#encodes)*
+            }
+        }
+    });
+
+    let json_arms = variants.iter().map(|v| {
+        let tag = v.tag;
+        let name = &v.name;
+        let len = v.types.len();
+        let encodes = v.types.iter().enumerate().map(|(i, ty)| {
+            quote! {
+                <#ty as #field_trait>::encode_field(
+                    &<#ty as #field_trait>::from_json(&data[#i])?,
+                    &mut out,
+                );
+            }
+        });
+        quote! {
+            #name => {
+                if data.len() != #len {
+                    return Err(#error::InvalidAction("Invalid data length"));
+                }
+                out.push(#tag);
+                #(#encodes)*
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::turbo_program::traits::TurboActionSerialization
+            for #ident #ty_generics #where_clause
+        {
+            fn deserialize(action: &[u8]) -> Result<(Self, &[u8]), #error> {
+                let (tag, rest) = action.split_first().ok_or(#error::decode("Unexpected end of action"))?;
+                match *tag {
+                    #(#deserialize_arms)*
+                    _ => Err(#error::decode("Invalid action type")),
+                }
+            }
+
+            fn serialize(&self) -> Vec<u8> {
+                let mut out = Vec::new();
+                match self {
+                    #(#serialize_arms)*
+                }
+                out
+            }
+
+            fn serialize_json(json_str: &str) -> Result<Vec<u8>, #error> {
+                let (action_type, data) = ::turbo_program::traits::parse_action_json(json_str)?;
+                let mut out = Vec::new();
+                match action_type.as_str() {
+                    #(#json_arms)*
+                    _ => return Err(#error::InvalidAction("Invalid action type")),
+                }
+                Ok(out)
+            }
+        }
+    })
+}
+
+fn parse_variant(variant: &Variant, default_tag: u16) -> syn::Result<ActionVariant> {
+    let mut tag = None;
+    let mut name = variant.ident.to_string();
+
+    for attr in &variant.attrs {
+        if !attr.path().is_ident("turbo_action") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                tag = Some(meta.value()?.parse::<LitInt>()?.base10_parse::<u8>()?);
+                Ok(())
+            } else if meta.path.is_ident("rename") {
+                name = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("unsupported turbo_action attribute"))
+            }
+        })?;
+    }
+
+    let tag = match tag {
+        Some(tag) => tag,
+        None => u8::try_from(default_tag).map_err(|_| {
+            syn::Error::new_spanned(&variant.ident, "too many actions, tags must fit in a u8")
+        })?,
+    };
+
+    let ident = variant.ident.clone();
+    let (bindings, types, pattern) = match &variant.fields {
+        Fields::Unit => (Vec::new(), Vec::new(), quote!(#ident)),
+        Fields::Unnamed(fields) => {
+            let bindings: Vec<Ident> = (0..fields.unnamed.len())
+                .map(|i| format_ident!("field_{}", i))
+                .collect();
+            let types = fields.unnamed.iter().map(|f| f.ty.clone()).collect();
+            let pattern = quote!(#ident(#(#bindings),*));
+            (bindings, types, pattern)
+        }
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|f| f.ident.clone().unwrap());
+            let bindings: Vec<Ident> = (0..fields.named.len())
+                .map(|i| format_ident!("field_{}", i))
+                .collect();
+            let types = fields.named.iter().map(|f| f.ty.clone()).collect();
+            let pattern = quote!(#ident { #(#names: #bindings),* });
+            (bindings, types, pattern)
+        }
+    };
+
+    Ok(ActionVariant {
+        ident,
+        tag,
+        name,
+        bindings,
+        types,
+        pattern,
+    })
+}
@@ -0,0 +1,74 @@
+use crate::{error::TurboError, transcript::TurboPublicValues};
+
+/*
+Aggregation Stdin Format:
+- Segment program vkey digest
+- Public values of every segment, in session order
+- One compressed proof per segment (written with `SP1Stdin::write_proof`)
+
+Public Values Format (ABI-encoded `TurboAggregatedPublicValues`)
+*/
+
+/// Check that segment public values chain into a single session and fold them into the public
+/// values of the whole session.
+///
+/// Each segment must continue from the checkpoint the previous one ended at, with the same server
+/// seed and the previous players as a prefix of its own. Action hashes carry over checkpoints so
+/// the last segment's hashes already cover the whole session.
+pub fn aggregate_segments(segments: &[TurboPublicValues]) -> Result<TurboPublicValues, TurboError> {
+    let (first, last) = match (segments.first(), segments.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Err(TurboError::Aggregation("No segments")),
+    };
+
+    for pair in segments.windows(2) {
+        let (prev, next) = (&pair[0], &pair[1]);
+        if next.priorStateHash != prev.stateHash {
+            return Err(TurboError::Aggregation(
+                "Segment does not continue from the previous state",
+            ));
+        }
+        if next.serverSeedCommitment != prev.serverSeedCommitment {
+            return Err(TurboError::Aggregation(
+                "Server seed changed between segments",
+            ));
+        }
+        if prev.finished {
+            return Err(TurboError::Aggregation("Segment continues a finished game"));
+        }
+        if next.configHash != prev.configHash {
+            return Err(TurboError::Aggregation("Config changed between segments"));
+        }
+        if next.playerCount < prev.playerCount
+            || next.playerSeeds.len() < prev.playerSeeds.len()
+            || next.playerSeeds[..prev.playerSeeds.len()] != prev.playerSeeds[..]
+        {
+            return Err(TurboError::Aggregation("Players changed between segments"));
+        }
+        if next.playerJoinedAt.len() < prev.playerJoinedAt.len()
+            || next.playerJoinedAt[..prev.playerJoinedAt.len()] != prev.playerJoinedAt[..]
+            || next.playerPublicKeys.len() < prev.playerPublicKeys.len()
+            || next.playerPublicKeys[..prev.playerPublicKeys.len()] != prev.playerPublicKeys[..]
+        {
+            return Err(TurboError::Aggregation("Players changed between segments"));
+        }
+    }
+
+    Ok(TurboPublicValues {
+        state: last.state.clone(),
+        priorStateHash: first.priorStateHash,
+        stateHash: last.stateHash,
+        serverSeedCommitment: last.serverSeedCommitment,
+        configHash: last.configHash,
+        finished: last.finished,
+        privateStateCommitment: last.privateStateCommitment,
+        playerCount: last.playerCount,
+        playerSeeds: last.playerSeeds.clone(),
+        actionHashes: last.actionHashes.clone(),
+        systemActionHash: last.systemActionHash,
+        playerJoinedAt: last.playerJoinedAt.clone(),
+        playerLeftAt: last.playerLeftAt.clone(),
+        playerPublicKeys: last.playerPublicKeys.clone(),
+        playerViews: last.playerViews.clone(),
+    })
+}
@@ -0,0 +1,63 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{crypto::keccak::keccak256, metadata::PlayerMetadata};
+
+/// Portable snapshot of a `TurboActionContextInner`, identical on host and zkVM.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurboContextCheckpoint {
+    pub action_hash: [u32; 8],
+    pub action_hash_shift: u32,
+    pub seed: [u32; 16],
+    pub rand_seed: [u32; 16],
+    pub rand_nonce: u64,
+    /// Named streams created so far, by label: current seed and nonce.
+    pub streams: Vec<(String, [u32; 16], u64)>,
+    pub joined_at: u64,
+    pub left_at: Option<u64>,
+    pub action_sequence: u64,
+}
+
+/// Portable snapshot of a `TurboSystemContextInner`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurboSystemCheckpoint {
+    pub sequence: u64,
+    pub timestamp: u64,
+    pub tick: u64,
+    pub action_hash: [u32; 8],
+    pub action_hash_shift: u32,
+    pub rand_seed: [u32; 16],
+    pub rand_nonce: u64,
+}
+
+/// Everything needed to resume a session: both states, every player that joined with its context
+/// and the system context.
+///
+/// A continuation proof reads a checkpoint instead of calling `TurboGame::init` and commits the
+/// hash of the checkpoint it started from as well as the hash of the checkpoint it ends at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurboCheckpoint<PublicState, PrivateState> {
+    pub public_state: PublicState,
+    pub private_state: PrivateState,
+    pub players: Vec<PlayerMetadata>,
+    pub contexts: Vec<TurboContextCheckpoint>,
+    pub system: TurboSystemCheckpoint,
+}
+
+impl<PublicState: Serialize, PrivateState: Serialize> TurboCheckpoint<PublicState, PrivateState> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("Failed to serialize checkpoint")
+    }
+}
+
+impl<PublicState: DeserializeOwned, PrivateState: DeserializeOwned>
+    TurboCheckpoint<PublicState, PrivateState>
+{
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        bincode::deserialize(bytes).ok()
+    }
+}
+
+/// Hash committed for a checkpoint, computed over its serialized bytes.
+pub fn checkpoint_hash(bytes: &[u8]) -> [u8; 32] {
+    keccak256(bytes)
+}
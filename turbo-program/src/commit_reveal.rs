@@ -0,0 +1,148 @@
+//! Commit-reveal of simultaneous moves.
+//!
+//! Each player first commits to a move with `move_commitment` (a hash of the move and a secret
+//! salt), then reveals the move and salt once everyone committed. `CommitReveal` is kept in the
+//! game state and checks both steps, the same way in `TurboSession` and in `turbo_program`.
+//!
+//! Reveals can be bounded by a deadline, a server timestamp: once it passes, reveals are refused and
+//! the game returns the deadline from `TurboGame::deadline` so the server dispatches a deadline
+//! action. `TurboGame::on_deadline` then applies penalties to `missing_reveals`, and the program
+//! refuses a deadline action stamped before the deadline.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{crypto::keccak::keccak256, error::GameError};
+
+const COMMITMENT_LABEL: &[u8] = b"turbo:move";
+
+/// Keccak-256 of a label, the player index, the salt and the move bytes.
+///
+/// The player index is part of the hash so a player cannot copy another player's commitment.
+pub fn move_commitment(player_index: usize, salt: &[u8; 32], move_bytes: &[u8]) -> [u8; 32] {
+    keccak256(
+        &[
+            COMMITMENT_LABEL,
+            &(player_index as u32).to_be_bytes(),
+            salt,
+            move_bytes,
+        ]
+        .concat(),
+    )
+}
+
+/// Commitments and reveals of one round of simultaneous moves, indexed by player.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitReveal {
+    commitments: Vec<Option<[u8; 32]>>,
+    reveals: Vec<Option<Vec<u8>>>,
+    deadline: Option<u64>,
+}
+
+impl CommitReveal {
+    pub fn new(player_count: usize) -> Self {
+        Self {
+            commitments: vec![None; player_count],
+            reveals: vec![None; player_count],
+            deadline: None,
+        }
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.commitments.len()
+    }
+
+    /// Make room for a player that joined after the round started.
+    pub fn add_player(&mut self) {
+        self.commitments.push(None);
+        self.reveals.push(None);
+    }
+
+    /// Store a player's commitment, refused once reveals started or if the player already committed.
+    pub fn commit(&mut self, player_index: usize, commitment: [u8; 32]) -> Result<(), GameError> {
+        if self.reveals.iter().any(Option::is_some) {
+            return Err(GameError::new("Reveals already started"));
+        }
+        match self.commitments.get_mut(player_index) {
+            Some(slot @ None) => {
+                *slot = Some(commitment);
+                Ok(())
+            }
+            Some(Some(_)) => Err(GameError::new("Move already committed")),
+            None => Err(GameError::new("Unknown player")),
+        }
+    }
+
+    /// Check a reveal against the player's commitment and store the move.
+    ///
+    /// `timestamp` is the server timestamp of the reveal, `context.timestamp()`.
+    pub fn reveal(
+        &mut self,
+        player_index: usize,
+        salt: &[u8; 32],
+        move_bytes: &[u8],
+        timestamp: u64,
+    ) -> Result<(), GameError> {
+        if self.deadline.is_some_and(|deadline| timestamp >= deadline) {
+            return Err(GameError::new("Reveal deadline passed"));
+        }
+        if !self.all_committed() {
+            return Err(GameError::new("Waiting for every player to commit"));
+        }
+        let (Some(reveal), Some(commitment)) = (
+            self.reveals.get_mut(player_index),
+            self.commitments.get(player_index),
+        ) else {
+            return Err(GameError::new("Unknown player"));
+        };
+        if reveal.is_some() {
+            return Err(GameError::new("Move already revealed"));
+        }
+        if *commitment != Some(move_commitment(player_index, salt, move_bytes)) {
+            return Err(GameError::new("Reveal does not match the commitment"));
+        }
+        *reveal = Some(move_bytes.to_vec());
+        Ok(())
+    }
+
+    /// Set the server timestamp reveals must happen before.
+    pub fn set_deadline(&mut self, deadline: u64) {
+        self.deadline = Some(deadline);
+    }
+
+    pub fn deadline(&self) -> Option<u64> {
+        self.deadline
+    }
+
+    pub fn is_committed(&self, player_index: usize) -> bool {
+        self.commitments
+            .get(player_index)
+            .is_some_and(Option::is_some)
+    }
+
+    pub fn all_committed(&self) -> bool {
+        self.commitments.iter().all(Option::is_some)
+    }
+
+    pub fn all_revealed(&self) -> bool {
+        self.reveals.iter().all(Option::is_some)
+    }
+
+    pub fn revealed(&self, player_index: usize) -> Option<&[u8]> {
+        self.reveals.get(player_index)?.as_deref()
+    }
+
+    /// Players that did not reveal their move, the ones to penalise at the deadline.
+    pub fn missing_reveals(&self) -> Vec<usize> {
+        self.reveals
+            .iter()
+            .enumerate()
+            .filter(|(_, reveal)| reveal.is_none())
+            .map(|(player_index, _)| player_index)
+            .collect()
+    }
+
+    /// Start the next round with the same players.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.player_count());
+    }
+}
@@ -1,32 +1,49 @@
+use std::{collections::BTreeMap, ops::Range};
+
 use serde_json::Value;
 
 use crate::{
-    crypto::fnv::FnvHasher,
+    checkpoint::{TurboContextCheckpoint, TurboSystemCheckpoint},
+    crypto::{action_hasher::ActionHasher, fnv::FnvHasher},
     metadata::{PlayerMetadata, ServerMetadata},
     rand::bn_randomizer::BnRandomizer,
 };
 
 #[derive(Clone)]
-pub struct TurboActionContextInner {
+pub struct TurboActionContextInner<H: ActionHasher = FnvHasher> {
     player_index: usize,
-    action_hash: FnvHasher,
+    action_hash: H,
+    // Combined server and player seed, named streams are derived from it
+    seed: [u32; 16],
     rand: BnRandomizer,
+    streams: BTreeMap<String, BnRandomizer>,
+    // Session sequence numbers of the join and of the leave, see `TurboSystemContextInner::sequence`
+    joined_at: u64,
+    left_at: Option<u64>,
+    // Actions of the player in the action stream, signed actions cover it
+    action_sequence: u64,
     pub client_response: Option<Value>,
 }
 
-impl TurboActionContextInner {
+impl<H: ActionHasher> TurboActionContextInner<H> {
     pub fn new(
         server_metadata: &ServerMetadata,
         player_metadata: &PlayerMetadata,
         player_index: usize,
     ) -> Self {
+        let rand = BnRandomizer::new_with_seeds(vec![
+            server_metadata.random_seed,
+            player_metadata.random_seed,
+        ]);
         let mut context = Self {
             player_index,
-            rand: BnRandomizer::new_with_seeds(vec![
-                server_metadata.random_seed,
-                player_metadata.random_seed,
-            ]),
-            action_hash: FnvHasher::new(),
+            seed: rand.current_seed(),
+            rand,
+            streams: BTreeMap::new(),
+            action_hash: H::new(),
+            joined_at: 0,
+            left_at: None,
+            action_sequence: 0,
             client_response: None,
         };
 
@@ -37,10 +54,78 @@ impl TurboActionContextInner {
         context
     }
 
+    pub fn from_checkpoint(checkpoint: &TurboContextCheckpoint, player_index: usize) -> Self {
+        Self {
+            player_index,
+            seed: checkpoint.seed,
+            rand: BnRandomizer::from_parts(checkpoint.rand_seed, checkpoint.rand_nonce),
+            streams: checkpoint
+                .streams
+                .iter()
+                .map(|(label, seed, nonce)| {
+                    (label.clone(), BnRandomizer::from_parts(*seed, *nonce))
+                })
+                .collect(),
+            action_hash: H::from_parts(checkpoint.action_hash, checkpoint.action_hash_shift),
+            joined_at: checkpoint.joined_at,
+            left_at: checkpoint.left_at,
+            action_sequence: checkpoint.action_sequence,
+            client_response: None,
+        }
+    }
+
+    pub fn checkpoint(&self) -> TurboContextCheckpoint {
+        TurboContextCheckpoint {
+            action_hash: self.action_hash.digest(),
+            action_hash_shift: self.action_hash.shift(),
+            seed: self.seed,
+            rand_seed: self.rand.current_seed(),
+            rand_nonce: self.rand.nonce(),
+            streams: self
+                .streams
+                .iter()
+                .map(|(label, rand)| (label.clone(), rand.current_seed(), rand.nonce()))
+                .collect(),
+            joined_at: self.joined_at,
+            left_at: self.left_at,
+            action_sequence: self.action_sequence,
+        }
+    }
+
     pub fn player_index(&self) -> usize {
         self.player_index
     }
 
+    pub fn joined_at(&self) -> u64 {
+        self.joined_at
+    }
+
+    pub fn left_at(&self) -> Option<u64> {
+        self.left_at
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.left_at.is_none()
+    }
+
+    /// Record a join after the game started, players present at `init` joined at 0.
+    pub fn mark_joined(&mut self, sequence: u64) {
+        self.joined_at = sequence;
+    }
+
+    pub fn mark_left(&mut self, sequence: u64) {
+        self.left_at = Some(sequence);
+    }
+
+    /// Sequence number the player's next action is signed with.
+    pub fn action_sequence(&self) -> u64 {
+        self.action_sequence
+    }
+
+    pub fn advance_action_sequence(&mut self) {
+        self.action_sequence += 1;
+    }
+
     pub fn rand_u32(&mut self) -> u32 {
         self.rand.next_u32()
     }
@@ -49,22 +134,36 @@ impl TurboActionContextInner {
         self.rand.next_u64()
     }
 
+    pub fn rand(&mut self) -> &mut BnRandomizer {
+        &mut self.rand
+    }
+
+    pub fn rng(&mut self, label: &str) -> &mut BnRandomizer {
+        let seed = self.seed;
+        self.streams
+            .entry(label.to_string())
+            .or_insert_with(|| BnRandomizer::new_with_label(&seed, label))
+    }
+
     pub fn action_hash(&self) -> [u32; 8] {
-        self.action_hash.get()
+        self.action_hash.digest()
     }
 
     pub fn update_action_hash(&mut self, action: &[u8]) {
-        self.action_hash.next(action);
+        self.action_hash.update(action);
     }
 }
 
-pub struct TurboActionContext<'a> {
+/// Context of `TurboGame::reduce`, `C` is the game's `TurboGame::Config`.
+pub struct TurboActionContext<'a, H: ActionHasher = FnvHasher, C = ()> {
     pub server_metadata: &'a ServerMetadata,
     pub player_metadata: &'a PlayerMetadata,
-    pub inner: TurboActionContextInner,
+    pub inner: TurboActionContextInner<H>,
+    timestamp: u64,
+    config: &'a C,
 }
 
-impl<'a> TurboActionContext<'a> {
+impl<'a, H: ActionHasher> TurboActionContext<'a, H> {
     pub fn new(
         server_metadata: &'a ServerMetadata,
         player_metadata: &'a PlayerMetadata,
@@ -74,25 +173,60 @@ impl<'a> TurboActionContext<'a> {
             server_metadata,
             player_metadata,
             inner: TurboActionContextInner::new(server_metadata, player_metadata, player_index),
+            timestamp: 0,
+            config: &(),
         }
     }
 
     pub fn new_from_inner(
         server_metadata: &'a ServerMetadata,
         player_metadata: &'a PlayerMetadata,
-        inner: TurboActionContextInner,
+        inner: TurboActionContextInner<H>,
     ) -> Self {
         Self {
             server_metadata,
             player_metadata,
             inner,
+            timestamp: 0,
+            config: &(),
         }
     }
 
+    /// Set the game configuration the session was created with.
+    pub fn with_config<C>(self, config: &'a C) -> TurboActionContext<'a, H, C> {
+        TurboActionContext {
+            server_metadata: self.server_metadata,
+            player_metadata: self.player_metadata,
+            inner: self.inner,
+            timestamp: self.timestamp,
+            config,
+        }
+    }
+}
+
+impl<'a, H: ActionHasher, C> TurboActionContext<'a, H, C> {
+    /// Set the server timestamp of the action being applied.
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Game configuration of the session.
+    pub fn config(&self) -> &C {
+        self.config
+    }
+
     pub fn player_index(&self) -> usize {
         self.inner.player_index()
     }
 
+    /// Server timestamp of the action, in milliseconds since the Unix epoch.
+    ///
+    /// Stamped by `TurboSession` and carried in the action stream, timestamps never decrease.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
     pub fn rand_u32(&mut self) -> u32 {
         self.inner.rand_u32()
     }
@@ -101,6 +235,50 @@ impl<'a> TurboActionContext<'a> {
         self.inner.rand_u64()
     }
 
+    /// The player's randomizer, it implements `rand_core::RngCore` so `rand` distributions and
+    /// `rand::seq` can be used in reducers.
+    pub fn rand(&mut self) -> &mut BnRandomizer {
+        self.inner.rand()
+    }
+
+    /// Named random stream, independent of `rand()` and of every other label.
+    ///
+    /// Each stream is derived from the combined seed and `label` and keeps its own nonce, so
+    /// draws from one stream never shift the values of another.
+    pub fn rng(&mut self, label: &str) -> &mut BnRandomizer {
+        self.inner.rng(label)
+    }
+
+    /// Uniform value in `range` without modulo bias, see `BnRandomizer::next_range`.
+    pub fn rand_range(&mut self, range: Range<u32>) -> u32 {
+        self.inner.rand.next_range(range)
+    }
+
+    /// `true` with probability `p`.
+    pub fn rand_bool(&mut self, p: f64) -> bool {
+        self.inner.rand.next_bool(p)
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        self.inner.rand.shuffle(items)
+    }
+
+    pub fn choose<'b, T>(&mut self, items: &'b [T]) -> Option<&'b T> {
+        self.inner.rand.choose(items)
+    }
+
+    pub fn weighted_index(&mut self, weights: &[u32]) -> Option<usize> {
+        self.inner.rand.weighted_index(weights)
+    }
+
+    pub fn choose_weighted<'b, T>(&mut self, items: &'b [T], weights: &[u32]) -> Option<&'b T> {
+        self.inner.rand.choose_weighted(items, weights)
+    }
+
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.inner.rand.fill_bytes(dest)
+    }
+
     pub fn action_hash(&self) -> [u32; 8] {
         self.inner.action_hash()
     }
@@ -113,3 +291,199 @@ impl<'a> TurboActionContext<'a> {
         &mut self.inner.client_response
     }
 }
+
+// Label of the server random stream, derived from the server seed alone
+const SYSTEM_STREAM_LABEL: &str = "turbo:system";
+
+/// State of the system action channel, shared by every system action of a session.
+#[derive(Clone)]
+pub struct TurboSystemContextInner<H: ActionHasher = FnvHasher> {
+    sequence: u64,
+    // Timestamp of the last accepted action
+    timestamp: u64,
+    tick: u64,
+    action_hash: H,
+    rand: BnRandomizer,
+}
+
+impl<H: ActionHasher> TurboSystemContextInner<H> {
+    pub fn new(server_metadata: &ServerMetadata) -> Self {
+        let rand = BnRandomizer::new_with_label(&server_metadata.random_seed, SYSTEM_STREAM_LABEL);
+        let mut context = Self {
+            sequence: 0,
+            timestamp: 0,
+            tick: 0,
+            action_hash: H::new(),
+            rand,
+        };
+
+        let current_bytes =
+            unsafe { std::mem::transmute::<[u32; 16], [u8; 64]>(context.rand.current_seed()) };
+        context.update_action_hash(&current_bytes);
+
+        context
+    }
+
+    pub fn from_checkpoint(checkpoint: &TurboSystemCheckpoint) -> Self {
+        Self {
+            sequence: checkpoint.sequence,
+            timestamp: checkpoint.timestamp,
+            tick: checkpoint.tick,
+            action_hash: H::from_parts(checkpoint.action_hash, checkpoint.action_hash_shift),
+            rand: BnRandomizer::from_parts(checkpoint.rand_seed, checkpoint.rand_nonce),
+        }
+    }
+
+    pub fn checkpoint(&self) -> TurboSystemCheckpoint {
+        TurboSystemCheckpoint {
+            sequence: self.sequence,
+            timestamp: self.timestamp,
+            tick: self.tick,
+            action_hash: self.action_hash.digest(),
+            action_hash_shift: self.action_hash.shift(),
+            rand_seed: self.rand.current_seed(),
+            rand_nonce: self.rand.nonce(),
+        }
+    }
+
+    /// Number of accepted actions of any kind: player actions, system actions, joins and leaves.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Timestamp of the last accepted action, actions with an older timestamp are refused.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Count an accepted action stamped with `timestamp`.
+    pub fn advance(&mut self, timestamp: u64) {
+        self.sequence += 1;
+        self.timestamp = timestamp;
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Count an accepted `TurboGame::SystemAction`.
+    pub fn advance_tick(&mut self) {
+        self.tick += 1;
+    }
+
+    pub fn rand(&mut self) -> &mut BnRandomizer {
+        &mut self.rand
+    }
+
+    pub fn action_hash(&self) -> [u32; 8] {
+        self.action_hash.digest()
+    }
+
+    pub fn update_action_hash(&mut self, action: &[u8]) {
+        self.action_hash.update(action);
+    }
+}
+
+/// Context of `TurboGame::reduce_system`, for actions dispatched by the server. `C` is the game's
+/// `TurboGame::Config`.
+pub struct TurboSystemContext<'a, H: ActionHasher = FnvHasher, C = ()> {
+    pub server_metadata: &'a ServerMetadata,
+    /// Players that joined before the action is applied, indexed by player index.
+    pub player_metadata: &'a [PlayerMetadata],
+    pub inner: TurboSystemContextInner<H>,
+    timestamp: u64,
+    config: &'a C,
+}
+
+impl<'a, H: ActionHasher> TurboSystemContext<'a, H> {
+    pub fn new_from_inner(
+        server_metadata: &'a ServerMetadata,
+        player_metadata: &'a [PlayerMetadata],
+        inner: TurboSystemContextInner<H>,
+    ) -> Self {
+        Self {
+            server_metadata,
+            player_metadata,
+            inner,
+            timestamp: 0,
+            config: &(),
+        }
+    }
+
+    /// Set the game configuration the session was created with.
+    pub fn with_config<C>(self, config: &'a C) -> TurboSystemContext<'a, H, C> {
+        TurboSystemContext {
+            server_metadata: self.server_metadata,
+            player_metadata: self.player_metadata,
+            inner: self.inner,
+            timestamp: self.timestamp,
+            config,
+        }
+    }
+}
+
+impl<'a, H: ActionHasher, C> TurboSystemContext<'a, H, C> {
+    /// Set the server timestamp of the action being applied.
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Game configuration of the session.
+    pub fn config(&self) -> &C {
+        self.config
+    }
+
+    /// Server timestamp of the action, in milliseconds since the Unix epoch.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Number of `TurboGame::SystemAction`s accepted before this one.
+    pub fn tick(&self) -> u64 {
+        self.inner.tick()
+    }
+
+    /// Number of actions of any kind accepted before this one.
+    pub fn sequence(&self) -> u64 {
+        self.inner.sequence()
+    }
+
+    /// Players that ever joined, including the ones that left.
+    pub fn player_count(&self) -> usize {
+        self.player_metadata.len()
+    }
+
+    /// The server random stream, derived from the server seed and independent of every player.
+    pub fn rand(&mut self) -> &mut BnRandomizer {
+        self.inner.rand()
+    }
+
+    pub fn rand_u32(&mut self) -> u32 {
+        self.inner.rand.next_u32()
+    }
+
+    pub fn rand_u64(&mut self) -> u64 {
+        self.inner.rand.next_u64()
+    }
+
+    pub fn rand_range(&mut self, range: Range<u32>) -> u32 {
+        self.inner.rand.next_range(range)
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        self.inner.rand.shuffle(items)
+    }
+
+    pub fn choose<'b, T>(&mut self, items: &'b [T]) -> Option<&'b T> {
+        self.inner.rand.choose(items)
+    }
+
+    pub fn action_hash(&self) -> [u32; 8] {
+        self.inner.action_hash()
+    }
+
+    pub fn update_action_hash(&mut self, action: &[u8]) {
+        self.inner.update_action_hash(action);
+    }
+}
@@ -0,0 +1,121 @@
+use sha2::{Digest, Sha256};
+
+use crate::crypto::{fnv::FnvHasher, keccak::keccak256};
+
+/// Running hash of the actions of one player, committed as its `actionHashes` entry.
+///
+/// Selected per game with `TurboGame::ActionHasher`. The context feeds it the combined seed once,
+/// then the server timestamp and bytes of every accepted action (without the sender), see
+/// `framing::action_hash_input`.
+pub trait ActionHasher: Clone + Send + Sync + 'static {
+    fn new() -> Self;
+    fn update(&mut self, data: &[u8]);
+    fn digest(&self) -> [u32; 8];
+
+    /// Backend specific position inside the digest, saved in checkpoints next to it.
+    fn shift(&self) -> u32 {
+        0
+    }
+
+    /// Restore a hasher from `digest` and `shift`.
+    fn from_parts(digest: [u32; 8], shift: u32) -> Self;
+}
+
+impl ActionHasher for FnvHasher {
+    fn new() -> Self {
+        FnvHasher::new()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.next(data);
+    }
+
+    fn digest(&self) -> [u32; 8] {
+        self.get()
+    }
+
+    fn shift(&self) -> u32 {
+        FnvHasher::shift(self) as u32
+    }
+
+    fn from_parts(digest: [u32; 8], shift: u32) -> Self {
+        FnvHasher::from_parts(digest, shift as usize)
+    }
+}
+
+/// Keccak-256 hash chain, `h = keccak256(h || data)` starting from 32 zero bytes.
+///
+/// In Solidity: `h = keccak256(abi.encodePacked(h, data))`. The digest words are the big-endian
+/// words of `h`.
+#[derive(Debug, Clone, Default)]
+pub struct KeccakHasher {
+    hash: [u8; 32],
+}
+
+impl ActionHasher for KeccakHasher {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.hash = keccak256(&[&self.hash[..], data].concat());
+    }
+
+    fn digest(&self) -> [u32; 8] {
+        bytes_to_words(&self.hash)
+    }
+
+    fn from_parts(digest: [u32; 8], _shift: u32) -> Self {
+        Self {
+            hash: words_to_bytes(&digest),
+        }
+    }
+}
+
+/// SHA-256 hash chain, `h = sha256(h || data)` starting from 32 zero bytes.
+///
+/// In Solidity: `h = sha256(abi.encodePacked(h, data))`. The digest words are the big-endian
+/// words of `h`.
+#[derive(Debug, Clone, Default)]
+pub struct Sha256Hasher {
+    hash: [u8; 32],
+}
+
+impl ActionHasher for Sha256Hasher {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        let mut hasher = Sha256::new();
+        hasher.update(self.hash);
+        hasher.update(data);
+        self.hash = hasher.finalize().into();
+    }
+
+    fn digest(&self) -> [u32; 8] {
+        bytes_to_words(&self.hash)
+    }
+
+    fn from_parts(digest: [u32; 8], _shift: u32) -> Self {
+        Self {
+            hash: words_to_bytes(&digest),
+        }
+    }
+}
+
+fn bytes_to_words(bytes: &[u8; 32]) -> [u32; 8] {
+    let mut words = [0u32; 8];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_be_bytes(chunk.try_into().unwrap());
+    }
+    words
+}
+
+fn words_to_bytes(words: &[u32; 8]) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    for (chunk, word) in bytes.chunks_exact_mut(4).zip(words.iter()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    bytes
+}
@@ -1,5 +1,3 @@
-use std::mem::transmute;
-
 use substrate_bn::{AffineG1, Fq, G1};
 
 // BN254 serialization
@@ -26,7 +24,11 @@ pub fn bn254_export_affine_g1_memcpy(affine: &AffineG1) -> [u32; 16] {
     let mut result = [0u32; 16];
 
     unsafe {
-        core::ptr::copy_nonoverlapping(transmute(affine), result.as_mut_ptr(), 16);
+        core::ptr::copy_nonoverlapping(
+            affine as *const AffineG1 as *const u32,
+            result.as_mut_ptr(),
+            16,
+        );
     }
 
     result
@@ -42,7 +44,7 @@ pub fn bn254_import_affine_g1(bytes: &[u8; 64]) -> AffineG1 {
 pub fn bn254_import_affine_g1_memcpy(data: &[u32; 16]) -> AffineG1 {
     let mut affine = AffineG1::zero();
     unsafe {
-        core::ptr::copy_nonoverlapping(data.as_ptr(), transmute(&mut affine), 16);
+        core::ptr::copy_nonoverlapping(data.as_ptr(), &mut affine as *mut AffineG1 as *mut u32, 16);
     }
     affine
 }
@@ -1,43 +1,33 @@
 #[cfg(not(target_os = "zkvm"))]
-use crypto_bigint::U256;
+use crypto_bigint::{Encoding, U256};
 
 #[cfg(target_os = "zkvm")]
 use sp1_lib::sys_bigint;
 
 // dd268dbcaac550362d98c384c4e576ccc8b1536847b6bbb31023b4c8caee0535
-#[cfg(target_os = "zkvm")]
 const OFFSET: [u32; 8] = [
     0xcaee0535, 0x1023b4c8, 0x47b6bbb3, 0xc8b15368, 0xc4e576cc, 0x2d98c384, 0xaac55036, 0xdd268dbc,
 ];
 
-#[cfg(target_os = "zkvm")]
+// 2^168 + 0x163
 const PRIME: [u32; 8] = [
     0x00000163, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000100, 0x00000000, 0x00000000,
 ];
 
-#[cfg(target_os = "zkvm")]
 const PRIME32: u32 = 0x01000193;
 
 #[cfg(target_os = "zkvm")]
 const MODULUS: [u32; 8] = [0, 0, 0, 0, 0, 0, 0, 0];
 
-#[cfg(not(target_os = "zkvm"))]
-const OFFSET_U256: U256 =
-    U256::from_be_hex("dd268dbcaac550362d98c384c4e576ccc8b1536847b6bbb31023b4c8caee0535");
-
-#[cfg(not(target_os = "zkvm"))]
-const PRIME_U256: U256 =
-    U256::from_be_hex("0000000000000000000001000000000000000000000000000000000000000163");
-
 // Modified FnvHasher that make it faster by hashing 8 bytes at a time
+//
+// The hash is kept as 8 little-endian u32 limbs on both host and zkVM. Every byte is mixed into
+// its own limb with a 32-bit FNV step, and every 8 bytes the whole 256-bit value is multiplied
+// by `PRIME` modulo 2^256. Only that multiplication differs between targets (`sys_bigint` in the
+// zkVM, `U256` on host), so both produce the same limbs.
 #[derive(Clone)]
 pub struct FnvHasher {
-    #[cfg(not(target_os = "zkvm"))]
-    hash: U256,
-
-    #[cfg(target_os = "zkvm")]
     hash: [u32; 8],
-
     shift: usize,
 }
 
@@ -50,44 +40,28 @@ impl Default for FnvHasher {
 impl FnvHasher {
     pub fn new() -> Self {
         Self {
-            #[cfg(not(target_os = "zkvm"))]
-            hash: OFFSET_U256,
-
-            #[cfg(target_os = "zkvm")]
             hash: OFFSET,
-
             shift: 0,
         }
     }
 
-    pub fn next_single(&mut self, data: u8) {
-        // println!("data: {:?}", data);
-
-        #[cfg(not(target_os = "zkvm"))]
-        {
-            if self.shift >= 8 {
-                self.hash = self.hash.wrapping_mul(&PRIME_U256);
-                self.shift = 0;
-            }
-            // TODO
-            self.hash = self.hash.wrapping_xor(&U256::from(data));
-            self.shift += 1;
-        }
+    /// Restore a hasher from the limbs returned by `get` and the number of bytes in the current word.
+    pub fn from_parts(hash: [u32; 8], shift: usize) -> Self {
+        Self { hash, shift }
+    }
 
-        #[cfg(target_os = "zkvm")]
-        {
-            if self.shift >= 8 {
-                unsafe {
-                    sys_bigint(&mut self.hash, 0, &self.hash, &PRIME, &MODULUS);
-                }
-                self.shift = 0;
-            }
+    pub fn shift(&self) -> usize {
+        self.shift
+    }
 
-            self.hash[self.shift] = (self.hash[self.shift] ^ (data as u32)) * PRIME32;
-            self.shift += 1;
+    pub fn next_single(&mut self, data: u8) {
+        if self.shift >= 8 {
+            self.mul_prime();
+            self.shift = 0;
         }
 
-        // println!("hash: {:?}", self.hash);
+        self.hash[self.shift] = (self.hash[self.shift] ^ (data as u32)).wrapping_mul(PRIME32);
+        self.shift += 1;
     }
 
     pub fn next(&mut self, data: &[u8]) {
@@ -97,19 +71,33 @@ impl FnvHasher {
     }
 
     pub fn get(&self) -> [u32; 8] {
+        self.hash
+    }
+
+    /// `hash = hash * PRIME mod 2^256`
+    fn mul_prime(&mut self) {
+        #[cfg(target_os = "zkvm")]
+        unsafe {
+            let hash = self.hash;
+            sys_bigint(&mut self.hash, 0, &hash, &PRIME, &MODULUS);
+        }
+
         #[cfg(not(target_os = "zkvm"))]
         {
-            let words = self.hash.to_words();
-            let mut result = [0u32; 8];
-            for i in 0..4 {
-                let bytes = words[i].to_le_bytes();
-                result[i * 2] = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
-                result[i * 2 + 1] = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+            let hash = limbs_to_u256(&self.hash).wrapping_mul(&limbs_to_u256(&PRIME));
+            let bytes = hash.to_le_bytes();
+            for (limb, chunk) in self.hash.iter_mut().zip(bytes.chunks_exact(4)) {
+                *limb = u32::from_le_bytes(chunk.try_into().unwrap());
             }
-            result
         }
+    }
+}
 
-        #[cfg(target_os = "zkvm")]
-        self.hash
+#[cfg(not(target_os = "zkvm"))]
+fn limbs_to_u256(limbs: &[u32; 8]) -> U256 {
+    let mut bytes = [0u8; 32];
+    for (chunk, limb) in bytes.chunks_exact_mut(4).zip(limbs.iter()) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
     }
+    U256::from_le_bytes(bytes)
 }
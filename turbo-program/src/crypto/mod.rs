@@ -1,3 +1,6 @@
+pub mod action_hasher;
 pub mod bn_math;
 pub mod bn_serialize;
 pub mod fnv;
+pub mod keccak;
+pub mod signature;
@@ -0,0 +1,137 @@
+use k256::ecdsa::signature::Verifier as _;
+use serde::{Deserialize, Serialize};
+
+use crate::error::TurboError;
+
+/// Length of every action signature: Ed25519 signatures and compact `r || s` secp256k1 ECDSA
+/// signatures are both 64 bytes.
+pub const SIGNATURE_LEN: usize = 64;
+
+/// Key a player signs actions with. A player with a key must sign every action.
+///
+/// Verification uses `ed25519-dalek` and `k256`, patched to use the SP1 precompiles in the zkVM.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlayerPublicKey {
+    Ed25519([u8; 32]),
+    /// SEC1 compressed point (33 bytes), signatures are ECDSA over SHA-256 of the message.
+    Secp256k1(Vec<u8>),
+}
+
+impl PlayerPublicKey {
+    const TAG_NONE: u8 = 0;
+    const TAG_ED25519: u8 = 1;
+    const TAG_SECP256K1: u8 = 2;
+
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        let Ok(signature) = <[u8; SIGNATURE_LEN]>::try_from(signature) else {
+            return false;
+        };
+
+        match self {
+            Self::Ed25519(key) => ed25519_dalek::VerifyingKey::from_bytes(key)
+                .map(|key| {
+                    key.verify_strict(message, &ed25519_dalek::Signature::from_bytes(&signature))
+                        .is_ok()
+                })
+                .unwrap_or(false),
+            Self::Secp256k1(key) => {
+                match (
+                    k256::ecdsa::VerifyingKey::from_sec1_bytes(key),
+                    k256::ecdsa::Signature::from_slice(&signature),
+                ) {
+                    (Ok(key), Ok(signature)) => key.verify(message, &signature).is_ok(),
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    /// Check the key is a point actions can be verified against: a valid Ed25519 key or a 33-byte
+    /// SEC1 compressed secp256k1 point, the only form a join action carries.
+    pub fn validate(&self) -> Result<(), TurboError> {
+        let valid = match self {
+            Self::Ed25519(key) => ed25519_dalek::VerifyingKey::from_bytes(key).is_ok(),
+            Self::Secp256k1(key) => {
+                key.len() == 33 && k256::PublicKey::from_sec1_bytes(key).is_ok()
+            }
+        };
+        if !valid {
+            return Err(TurboError::InvalidAction("Invalid public key"));
+        }
+        Ok(())
+    }
+
+    /// Append an optional key to a join action: a tag byte followed by the key bytes.
+    pub fn encode_optional(key: Option<&Self>, out: &mut Vec<u8>) {
+        match key {
+            None => out.push(Self::TAG_NONE),
+            Some(Self::Ed25519(key)) => {
+                out.push(Self::TAG_ED25519);
+                out.extend_from_slice(key);
+            }
+            Some(Self::Secp256k1(key)) => {
+                out.push(Self::TAG_SECP256K1);
+                out.extend_from_slice(key);
+            }
+        }
+    }
+
+    pub fn decode_optional(data: &[u8]) -> Result<(Option<Self>, &[u8]), TurboError> {
+        let (tag, rest) = data
+            .split_first()
+            .ok_or(TurboError::decode("Unexpected end of action"))?;
+        let (key, len) = match *tag {
+            Self::TAG_NONE => return Ok((None, rest)),
+            Self::TAG_ED25519 => (
+                rest.get(..32)
+                    .map(|key| Self::Ed25519(key.try_into().unwrap())),
+                32,
+            ),
+            Self::TAG_SECP256K1 => (rest.get(..33).map(|key| Self::Secp256k1(key.to_vec())), 33),
+            _ => return Err(TurboError::decode("Invalid public key type")),
+        };
+        match key {
+            Some(key) => Ok((Some(key), &rest[len..])),
+            None => Err(TurboError::Decode {
+                offset: data.len(),
+                message: "Unexpected end of action",
+            }),
+        }
+    }
+
+    /// Bytes committed in `TurboPublicValues::playerPublicKeys`, empty for players without a key.
+    pub fn commitment(key: Option<&Self>) -> Vec<u8> {
+        match key {
+            None => Vec::new(),
+            Some(_) => {
+                let mut out = Vec::new();
+                Self::encode_optional(key, &mut out);
+                out
+            }
+        }
+    }
+}
+
+/// Split the signature trailing the action bytes of a player with a public key.
+pub fn split_signature(data: &[u8]) -> Result<(&[u8], &[u8]), TurboError> {
+    if data.len() < SIGNATURE_LEN {
+        return Err(TurboError::Decode {
+            offset: data.len(),
+            message: "Missing action signature",
+        });
+    }
+    Ok(data.split_at(SIGNATURE_LEN))
+}
+
+/// Message signed for an action: the session id, the player's sequence number (accepted actions of
+/// the player so far, big-endian) and the action bytes.
+///
+/// The session id is the server seed commitment, published before any player joins and unique to
+/// the session, so signatures cannot be replayed in another session or at another position.
+pub fn action_signing_message(session_id: &[u8; 32], sequence: u64, action: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 + 8 + action.len());
+    message.extend_from_slice(session_id);
+    message.extend_from_slice(&sequence.to_be_bytes());
+    message.extend_from_slice(action);
+    message
+}
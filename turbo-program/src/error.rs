@@ -0,0 +1,145 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TurboError {
+    /// Malformed action bytes, `offset` is the byte position where decoding failed.
+    Decode {
+        offset: usize,
+        message: &'static str,
+    },
+    /// An action (or a batch of actions) could not be built from client input.
+    InvalidAction(&'static str),
+    /// The game configuration sent by a client is not a valid `TurboGame::Config`.
+    InvalidConfig(String),
+    UnknownPlayer(usize),
+    /// The player left the session and can no longer act.
+    PlayerLeft(usize),
+    /// The action of a player with a public key is not signed by that key.
+    InvalidSignature(usize),
+    /// The reducer rejected the action, the session state is unchanged.
+    ActionRejected(String),
+    /// The reducer panicked while processing an action.
+    ReducerPanic(String),
+    /// A previous reducer panic left the session in an unknown state.
+    SessionBricked,
+    /// The server seed was revealed, the session no longer accepts actions.
+    SeedRevealed,
+    /// The game reached a terminal state, the session no longer accepts actions.
+    SessionFinished,
+    /// Only a finished game reveals its server seed and private state.
+    SessionNotFinished,
+    SessionNotFound,
+    Setup(String),
+    Prover(String),
+    Execution(String),
+    /// Segment proofs do not chain into a single session.
+    Aggregation(&'static str),
+}
+
+impl TurboError {
+    pub fn decode(message: &'static str) -> Self {
+        Self::Decode { offset: 0, message }
+    }
+
+    /// Shift the offset of a decode error by `base` bytes.
+    ///
+    /// Decoders report offsets relative to the slice they were given, callers working on a
+    /// larger buffer use this to make the offset relative to their own start.
+    pub fn at_offset(self, base: usize) -> Self {
+        match self {
+            Self::Decode { offset, message } => Self::Decode {
+                offset: base + offset,
+                message,
+            },
+            other => other,
+        }
+    }
+
+    /// A stable machine readable name for the error kind.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Decode { .. } => "decode_error",
+            Self::InvalidAction(_) => "invalid_action",
+            Self::InvalidConfig(_) => "invalid_config",
+            Self::UnknownPlayer(_) => "unknown_player",
+            Self::PlayerLeft(_) => "player_left",
+            Self::InvalidSignature(_) => "invalid_signature",
+            Self::ActionRejected(_) => "action_rejected",
+            Self::ReducerPanic(_) => "reducer_panic",
+            Self::SessionBricked => "session_bricked",
+            Self::SeedRevealed => "seed_revealed",
+            Self::SessionFinished => "session_finished",
+            Self::SessionNotFinished => "session_not_finished",
+            Self::SessionNotFound => "session_not_found",
+            Self::Setup(_) => "setup_error",
+            Self::Prover(_) => "prover_error",
+            Self::Execution(_) => "execution_error",
+            Self::Aggregation(_) => "aggregation_error",
+        }
+    }
+}
+
+impl fmt::Display for TurboError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode { offset, message } => {
+                write!(f, "Failed to decode action at byte {}: {}", offset, message)
+            }
+            Self::InvalidAction(message) => write!(f, "Invalid action: {}", message),
+            Self::InvalidConfig(message) => write!(f, "Invalid game config: {}", message),
+            Self::UnknownPlayer(player_idx) => write!(f, "Unknown player {}", player_idx),
+            Self::PlayerLeft(player_idx) => write!(f, "Player {} left the session", player_idx),
+            Self::InvalidSignature(player_idx) => {
+                write!(f, "Invalid signature for player {}", player_idx)
+            }
+            Self::ActionRejected(message) => write!(f, "Action rejected: {}", message),
+            Self::ReducerPanic(message) => write!(f, "Reducer panicked: {}", message),
+            Self::SessionBricked => write!(f, "Session is bricked by a previous failed action"),
+            Self::SeedRevealed => write!(f, "Server seed has been revealed, session is closed"),
+            Self::SessionFinished => write!(f, "Game is over, session is closed"),
+            Self::SessionNotFinished => write!(f, "Game is not over yet"),
+            Self::SessionNotFound => write!(f, "Session not found"),
+            Self::Setup(message) => write!(f, "Failed to setup circuit: {}", message),
+            Self::Prover(message) => write!(f, "Failed to generate proof: {}", message),
+            Self::Execution(message) => write!(f, "Failed to execute circuit: {}", message),
+            Self::Aggregation(message) => write!(f, "Failed to aggregate segments: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for TurboError {}
+
+/// Returned by `TurboGame::reduce` to reject an action.
+///
+/// A rejected action is skipped: the state, the player context and the action hash are restored,
+/// both in `TurboSession` and in the zkVM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameError {
+    message: String,
+}
+
+impl GameError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<GameError> for TurboError {
+    fn from(error: GameError) -> Self {
+        Self::ActionRejected(error.message)
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{error::TurboError, traits::TurboActionField};
+
+/// Version byte written in front of the actions passed to `turbo_program`.
+pub const ACTION_FRAMING_VERSION: u8 = 2;
+
+/// Sender ids reserved for system actions.
+pub const SYSTEM_SENDER_IDS: std::ops::Range<u64> = 0x70..0x80;
+
+/// System action kind of `TurboGame::SystemAction`, the action bytes follow the timestamp.
+pub const SYSTEM_GAME_ACTION: u8 = 0;
+/// A player joins, the player's random seed (16 big-endian u32) and optional public key (see
+/// `PlayerPublicKey::encode_optional`) follow the timestamp.
+pub const SYSTEM_JOIN: u8 = 1;
+/// A player leaves, the player index (big-endian u32) follows the timestamp.
+pub const SYSTEM_LEAVE: u8 = 2;
+/// The deadline of `TurboGame::deadline` passed, nothing follows the timestamp. Only valid with a
+/// timestamp at or after the deadline.
+pub const SYSTEM_DEADLINE: u8 = 3;
+
+// Sender ids are at most a u32, so a varint is at most 5 bytes
+const MAX_SENDER_ID: u64 = u32::MAX as u64;
+const MAX_SENDER_LEN: usize = 5;
+
+/*
+Action Framing (version 2):
+- Version byte
+- Actions, each one:
+    - Sender id (unsigned LEB128, minimal encoding)
+    - Server timestamp (big-endian u64, milliseconds since the Unix epoch, never decreasing)
+    - Action bytes (`TurboActionSerialization`)
+    - Signature (64 bytes, only for players with a public key, see `crypto::signature`)
+
+Clients send actions without the timestamp, `TurboSession` stamps them when they are dispatched.
+
+Sender ids `0x70..0x80` are reserved for system actions, players take the remaining ids in
+order: players below 0x70 keep their one byte id and player `p >= 0x70` has id `p + 0x10`.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionSender {
+    Player(usize),
+    /// A system action, the kind is the offset in `SYSTEM_SENDER_IDS`.
+    System(u8),
+}
+
+impl ActionSender {
+    fn id(&self) -> u64 {
+        match *self {
+            Self::Player(index) if (index as u64) < SYSTEM_SENDER_IDS.start => index as u64,
+            Self::Player(index) => {
+                (index as u64).saturating_add(SYSTEM_SENDER_IDS.end - SYSTEM_SENDER_IDS.start)
+            }
+            Self::System(kind) => SYSTEM_SENDER_IDS.start + kind as u64,
+        }
+    }
+
+    fn from_id(id: u64) -> Self {
+        if id < SYSTEM_SENDER_IDS.start {
+            Self::Player(id as usize)
+        } else if id < SYSTEM_SENDER_IDS.end {
+            Self::System((id - SYSTEM_SENDER_IDS.start) as u8)
+        } else {
+            Self::Player((id - (SYSTEM_SENDER_IDS.end - SYSTEM_SENDER_IDS.start)) as usize)
+        }
+    }
+
+    /// Fails for a player index past the largest sender id, leaving `out` untouched.
+    pub fn encode(&self, out: &mut Vec<u8>) -> Result<(), TurboError> {
+        let mut id = self.id();
+        if id > MAX_SENDER_ID {
+            return Err(match *self {
+                Self::Player(index) => TurboError::UnknownPlayer(index),
+                Self::System(_) => TurboError::InvalidAction("Unknown system action"),
+            });
+        }
+        while id >= 0x80 {
+            out.push((id as u8 & 0x7f) | 0x80);
+            id >>= 7;
+        }
+        out.push(id as u8);
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, TurboError> {
+        let mut out = Vec::new();
+        self.encode(&mut out)?;
+        Ok(out)
+    }
+
+    /// Decode the sender in front of an action, returning the remaining bytes.
+    pub fn decode(data: &[u8]) -> Result<(Self, &[u8]), TurboError> {
+        let mut id: u64 = 0;
+        for (i, byte) in data.iter().enumerate() {
+            // Zero continuation bytes never raise the id, the length has to be capped on its own
+            if i >= MAX_SENDER_LEN {
+                return Err(TurboError::Decode {
+                    offset: i,
+                    message: "Sender id out of range",
+                });
+            }
+            id |= ((byte & 0x7f) as u64) << (7 * i);
+            if id > MAX_SENDER_ID {
+                return Err(TurboError::Decode {
+                    offset: i,
+                    message: "Sender id out of range",
+                });
+            }
+            if byte & 0x80 == 0 {
+                // A zero last byte could have been left out, only the minimal encoding is valid
+                if i > 0 && *byte == 0 {
+                    return Err(TurboError::Decode {
+                        offset: i,
+                        message: "Non minimal sender encoding",
+                    });
+                }
+                return Ok((Self::from_id(id), &data[i + 1..]));
+            }
+        }
+        Err(TurboError::Decode {
+            offset: data.len(),
+            message: "Unexpected end of action",
+        })
+    }
+}
+
+/// Prefix encoded actions with the framing version.
+pub fn encode_actions(actions: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(actions.len() + 1);
+    framed.push(ACTION_FRAMING_VERSION);
+    framed.extend_from_slice(actions);
+    framed
+}
+
+/// Check the framing version and return the encoded actions.
+pub fn decode_actions(framed: &[u8]) -> Result<&[u8], TurboError> {
+    match framed.split_first() {
+        Some((&ACTION_FRAMING_VERSION, actions)) => Ok(actions),
+        Some(_) => Err(TurboError::decode("Unsupported action framing version")),
+        None => Err(TurboError::decode("Missing action framing version")),
+    }
+}
+
+/// Insert `timestamp` after the sender of an action sent by a client.
+pub fn stamp_action(action_raw: &[u8], timestamp: u64) -> Result<Vec<u8>, TurboError> {
+    let (_, action_bytes) = ActionSender::decode(action_raw)?;
+    let sender_len = action_raw.len() - action_bytes.len();
+
+    let mut stamped = Vec::with_capacity(action_raw.len() + 8);
+    stamped.extend_from_slice(&action_raw[..sender_len]);
+    timestamp.encode_field(&mut stamped);
+    stamped.extend_from_slice(action_bytes);
+    Ok(stamped)
+}
+
+/// Remove the timestamp of a stamped action, e.g. to replay a recorded action stream.
+pub fn unstamp_action(frame: &[u8]) -> Result<(u64, Vec<u8>), TurboError> {
+    let (_, timestamp, action_bytes) = decode_stamped(frame)?;
+    let sender_len = frame.len() - action_bytes.len() - 8;
+    Ok((timestamp, [&frame[..sender_len], action_bytes].concat()))
+}
+
+/// Bytes a player's action hash is updated with: the server timestamp (big-endian u64) followed
+/// by the action bytes, so the proof commits to when every action was taken.
+pub fn action_hash_input(timestamp: u64, action_bytes: &[u8]) -> Vec<u8> {
+    let mut input = Vec::with_capacity(8 + action_bytes.len());
+    timestamp.encode_field(&mut input);
+    input.extend_from_slice(action_bytes);
+    input
+}
+
+/// Split a stamped action into its sender, timestamp and action bytes.
+pub fn decode_stamped(frame: &[u8]) -> Result<(ActionSender, u64, &[u8]), TurboError> {
+    let (sender, rest) = ActionSender::decode(frame)?;
+    let (timestamp, action_bytes) =
+        u64::decode_field(rest).map_err(|e| e.at_offset(frame.len() - rest.len()))?;
+    Ok((sender, timestamp, action_bytes))
+}
@@ -0,0 +1,174 @@
+use alloy_sol_types::{SolType, SolValue};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    context::{TurboActionContext, TurboSystemContext},
+    crypto::action_hasher::ActionHasher,
+    error::{GameError, TurboError},
+    metadata::PlayerMetadata,
+    traits::TurboActionSerialization,
+};
+
+/// A game that can be run by `TurboSession` and proven by `turbo_program`.
+///
+/// Implementors are usually unit structs, all hooks are associated functions so the same game
+/// definition is shared by the native session and the zkVM program.
+pub trait TurboGame: Send + Sync + 'static {
+    type PublicState: SolValue
+        + From<<<Self::PublicState as SolValue>::SolType as SolType>::RustType>
+        + Serialize
+        + DeserializeOwned
+        + Clone
+        + Send
+        + Sync;
+    type PrivateState: Serialize + DeserializeOwned + Clone + Send + Sync;
+    /// What the proof commits as the `state` of the public values, the public state itself or a
+    /// summary of it, see `TurboOutput`.
+    type Output: TurboOutput<Self::PublicState, Self::PrivateState>;
+    /// What a single player may see, served to that player instead of the public state, see
+    /// `TurboPlayerView`.
+    type PlayerView: TurboPlayerView<Self::PublicState, Self::PrivateState>;
+    type Action: TurboActionSerialization + Send + Sync;
+    /// Actions dispatched by the server rather than a player: timers, ticks, round ends.
+    /// `NoSystemAction` for games without any.
+    type SystemAction: TurboActionSerialization + Send + Sync;
+    /// Game settings (board size, difficulty, mode) fixed when the session is created, passed to
+    /// `init`, readable through `context.config()` and committed as `configHash`.
+    type Config: Serialize + DeserializeOwned + Default + Clone + Send + Sync + 'static;
+    /// Hash committed for every player's actions, `FnvHasher` or one that contracts and clients
+    /// can recompute such as `KeccakHasher` or `Sha256Hasher`.
+    type ActionHasher: ActionHasher;
+
+    /// Commit every player's view encrypted to the player's public key, so the proof attests to
+    /// what each player was shown. See `view`.
+    const ENCRYPTED_VIEWS: bool = false;
+
+    /// Build the initial state from the game configuration and the players present at start.
+    fn init(
+        config: &Self::Config,
+        players: &[PlayerMetadata],
+    ) -> (Self::PublicState, Self::PrivateState);
+
+    /// Apply an action, returning an error rejects it without failing the session or the proof.
+    /// The reducer runs on a copy of the state, so changes made before rejecting are discarded.
+    fn reduce(
+        public_state: &mut Self::PublicState,
+        private_state: &mut Self::PrivateState,
+        action: &Self::Action,
+        context: &mut TurboActionContext<Self::ActionHasher, Self::Config>,
+    ) -> Result<(), GameError>;
+
+    /// Apply a system action, rejected the same way as player actions. Rejects everything by default.
+    fn reduce_system(
+        _public_state: &mut Self::PublicState,
+        _private_state: &mut Self::PrivateState,
+        _action: &Self::SystemAction,
+        _context: &mut TurboSystemContext<Self::ActionHasher, Self::Config>,
+    ) -> Result<(), GameError> {
+        Err(GameError::new("System actions are not supported"))
+    }
+
+    /// Called when a player joins after the game started, `context.player_metadata` already
+    /// includes the player. Players present at start are passed to `init` instead.
+    fn on_join(
+        _public_state: &mut Self::PublicState,
+        _private_state: &mut Self::PrivateState,
+        _player_index: usize,
+        _context: &mut TurboSystemContext<Self::ActionHasher, Self::Config>,
+    ) {
+    }
+
+    /// Called when a player leaves, the player's later actions are refused.
+    fn on_leave(
+        _public_state: &mut Self::PublicState,
+        _private_state: &mut Self::PrivateState,
+        _player_index: usize,
+        _context: &mut TurboSystemContext<Self::ActionHasher, Self::Config>,
+    ) {
+    }
+
+    /// Server timestamp the game waits for, e.g. the end of a reveal phase (see `commit_reveal`).
+    /// Once it passes the server dispatches a deadline action that runs `on_deadline`.
+    fn deadline(
+        _public_state: &Self::PublicState,
+        _private_state: &Self::PrivateState,
+    ) -> Option<u64> {
+        None
+    }
+
+    /// Called by the deadline action once `deadline` passed, to penalise players that did not act
+    /// in time. It must clear or move the deadline, a deadline that stays due is dispatched again.
+    fn on_deadline(
+        _public_state: &mut Self::PublicState,
+        _private_state: &mut Self::PrivateState,
+        _context: &mut TurboSystemContext<Self::ActionHasher, Self::Config>,
+    ) {
+    }
+
+    /// Whether the game is over. Once it is, `TurboSession` refuses further actions and the
+    /// program refuses a transcript with actions after that point.
+    fn is_terminal(_public_state: &Self::PublicState, _private_state: &Self::PrivateState) -> bool {
+        false
+    }
+}
+
+/// Output committed by `turbo_program` at the end of a proof, ABI-encoded, and decoded back by the
+/// server from the public values.
+///
+/// Every public state is its own output. Games that only need part of it on chain (a winner, a
+/// score, a state hash) commit a summary type instead, `TurboSession` still serves the full state.
+pub trait TurboOutput<PublicState, PrivateState>:
+    SolValue + From<<<Self as SolValue>::SolType as SolType>::RustType> + Serialize + Send + Sync
+{
+    fn from_state(public_state: &PublicState, private_state: &PrivateState) -> Self;
+}
+
+impl<T, PrivateState> TurboOutput<T, PrivateState> for T
+where
+    T: SolValue + From<<<T as SolValue>::SolType as SolType>::RustType> + Serialize + Clone,
+    T: Send + Sync,
+{
+    fn from_state(public_state: &T, _private_state: &PrivateState) -> Self {
+        public_state.clone()
+    }
+}
+
+/// What a player is shown of the game.
+///
+/// Every public state is its own view. Games with hidden information (cards, fog of war) build a
+/// view per player instead, from the public state and the part of the private state the player
+/// may know.
+pub trait TurboPlayerView<PublicState, PrivateState>: Serialize + Send + Sync {
+    fn for_player(
+        public_state: &PublicState,
+        private_state: &PrivateState,
+        player_index: usize,
+    ) -> Self;
+}
+
+impl<T, PrivateState> TurboPlayerView<T, PrivateState> for T
+where
+    T: Serialize + Clone + Send + Sync,
+{
+    fn for_player(public_state: &T, _private_state: &PrivateState, _player_index: usize) -> Self {
+        public_state.clone()
+    }
+}
+
+/// `TurboGame::SystemAction` of games without system actions, it can never be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoSystemAction {}
+
+impl TurboActionSerialization for NoSystemAction {
+    fn deserialize(_action: &[u8]) -> Result<(Self, &[u8]), TurboError> {
+        Err(TurboError::decode("Game has no system actions"))
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        match *self {}
+    }
+
+    fn serialize_json(_json_str: &str) -> Result<Vec<u8>, TurboError> {
+        Err(TurboError::InvalidAction("Game has no system actions"))
+    }
+}
@@ -1,9 +1,18 @@
+pub mod aggregation;
+pub mod checkpoint;
+pub mod commit_reveal;
 pub mod context;
 pub mod crypto;
+pub mod error;
+pub mod framing;
+pub mod game;
 pub mod metadata;
+pub mod private_state;
 pub mod program;
 pub mod rand;
 pub mod traits;
+pub mod transcript;
+pub mod view;
 
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
@@ -1,11 +1,40 @@
 use serde::{Deserialize, Serialize};
 
+use crate::{crypto::signature::PlayerPublicKey, rand::provably_fair::commit_server_seed};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerMetadata {
     pub random_seed: [u32; 16],
 }
 
+impl ServerMetadata {
+    /// Commitment to the server seed, see `rand::provably_fair`.
+    pub fn seed_commitment(&self) -> [u8; 32] {
+        commit_server_seed(&self.random_seed)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerMetadata {
     pub random_seed: [u32; 16],
+    /// Key the player signs actions with, anyone can act as a player without one.
+    #[serde(default)]
+    pub public_key: Option<PlayerPublicKey>,
+}
+
+impl PlayerMetadata {
+    pub fn new(random_seed: [u32; 16]) -> Self {
+        Self {
+            random_seed,
+            public_key: None,
+        }
+    }
+}
+
+pub fn seed_to_bytes(seed: &[u32; 16]) -> [u8; 64] {
+    let mut bytes = [0u8; 64];
+    for (chunk, word) in bytes.chunks_exact_mut(4).zip(seed.iter()) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    bytes
 }
@@ -0,0 +1,59 @@
+//! Commitment to the private state.
+//!
+//! `turbo_program` commits a salted hash of the final private state next to the public output,
+//! so hidden information (hands, fog of war) can be revealed once the match is over and checked
+//! against the proof. The salt is derived from the server seed: players cannot brute force a small
+//! private state from the commitment, and revealing the salt does not reveal the seed.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    crypto::keccak::keccak256,
+    metadata::{seed_to_bytes, ServerMetadata},
+};
+
+const SALT_LABEL: &[u8] = b"turbo:private_state";
+
+/// Salt of the private state commitment, Keccak-256 of a label and the server seed bytes.
+pub fn private_state_salt(server_metadata: &ServerMetadata) -> [u8; 32] {
+    keccak256(&[SALT_LABEL, &seed_to_bytes(&server_metadata.random_seed)].concat())
+}
+
+/// Keccak-256 of the salt followed by the bincode serialized private state.
+pub fn commit_private_state(private_state: &[u8], salt: &[u8; 32]) -> [u8; 32] {
+    keccak256(&[&salt[..], private_state].concat())
+}
+
+pub fn private_state_bytes<S: Serialize>(private_state: &S) -> Vec<u8> {
+    bincode::serialize(private_state).expect("Failed to serialize private state")
+}
+
+/// A revealed private state together with the salt and the commitment from the public values.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrivateStateReveal {
+    pub private_state: Vec<u8>,
+    pub salt: [u8; 32],
+    pub commitment: [u8; 32],
+}
+
+impl PrivateStateReveal {
+    pub fn new<S: Serialize>(private_state: &S, server_metadata: &ServerMetadata) -> Self {
+        let private_state = private_state_bytes(private_state);
+        let salt = private_state_salt(server_metadata);
+        Self {
+            commitment: commit_private_state(&private_state, &salt),
+            private_state,
+            salt,
+        }
+    }
+
+    /// Check the reveal against its own commitment, compare `commitment` with the one in the
+    /// proven public values as well.
+    pub fn verify(&self) -> bool {
+        commit_private_state(&self.private_state, &self.salt) == self.commitment
+    }
+
+    pub fn decode<S: DeserializeOwned>(&self) -> Option<S> {
+        bincode::deserialize(&self.private_state).ok()
+    }
+}
@@ -1,96 +1,475 @@
 use alloy_sol_types::SolValue;
 
 use crate::{
-    context::{TurboActionContext, TurboActionContextInner},
+    checkpoint::{checkpoint_hash, TurboCheckpoint},
+    context::{
+        TurboActionContext, TurboActionContextInner, TurboSystemContext, TurboSystemContextInner,
+    },
+    crypto::signature::{action_signing_message, split_signature, PlayerPublicKey},
+    error::TurboError,
+    framing::{
+        action_hash_input, decode_actions, decode_stamped, ActionSender, SYSTEM_DEADLINE,
+        SYSTEM_GAME_ACTION, SYSTEM_JOIN, SYSTEM_LEAVE,
+    },
+    game::{TurboGame, TurboOutput},
     metadata::{PlayerMetadata, ServerMetadata},
-    traits::TurboActionSerialization,
+    private_state::{commit_private_state, private_state_bytes, private_state_salt},
+    traits::{TurboActionField, TurboActionSerialization},
+    transcript::{config_hash, TurboPublicValues},
+    view::encrypted_player_views,
 };
 
-pub type TurboReducer<PublicState, PrivateState, GameAction> = fn(
-    public_state: &mut PublicState,
-    private_state: &mut PrivateState,
-    action: &GameAction,
-    context: &mut TurboActionContext,
-);
-
 /*
 Stdin Format:
 - Server Metadata
     - Server Random Seed
-- Players Metadata
+- Game Config
+- Initial Players Metadata (players passed to `TurboGame::init`, ignored with a prior checkpoint)
     - Client Seed
-- Actions
+    - Public Key (optional, the player signs every action with it)
+- Prior Checkpoint (optional, bincode `TurboCheckpoint` to continue from instead of `init`)
+- Actions (versioned framing with server timestamps, see `framing`), later players join and
+  leave with system actions, and passed deadlines are system actions as well
+- View secret (random, never revealed, the keys of encrypted views are derived from it, see `view`)
+- Trace (false for proofs, true commits the state hash trace of `trace_turbo_program` instead of
+  the public values, to find where the program diverges from `TurboSession`)
+
+Public Values Format (ABI-encoded `TurboPublicValues`):
+- ABI-encoded `TurboGame::Output` of the final state
+- Prior checkpoint hash (zero when starting from `init`) and final checkpoint hash
+- Server seed commitment
+- Game config hash
+- Whether the game reached its terminal state
+- Salted commitment to the final private state
+- Player count and seeds of every player that joined
+- Final action hash of every player
+- Final system action hash
+- Sequence numbers at which every player joined and left
+- Public key of every player (empty for players without one)
+- Every player's view encrypted to the player's key, with `TurboGame::ENCRYPTED_VIEWS`
 */
 
-fn turbo_program_inner<PublicState, PrivateState, GameAction>(
-    reducer: TurboReducer<PublicState, PrivateState, GameAction>,
+/// Everything the actions advance: both states, every player with its context and the system
+/// context.
+struct TurboProgramState<G: TurboGame> {
+    public_state: G::PublicState,
+    private_state: G::PrivateState,
+    player_metadata: Vec<PlayerMetadata>,
+    contexts: Vec<TurboActionContextInner<G::ActionHasher>>,
+    system: TurboSystemContextInner<G::ActionHasher>,
+}
+
+impl<G: TurboGame> TurboProgramState<G> {
+    fn new(
+        server_metadata: &ServerMetadata,
+        config: &G::Config,
+        initial_player_metadata: Vec<PlayerMetadata>,
+        prior_checkpoint: Option<&[u8]>,
+    ) -> Self {
+        // Start from the prior checkpoint if there is one, otherwise from a fresh game
+        match prior_checkpoint {
+            Some(bytes) => {
+                let checkpoint =
+                    TurboCheckpoint::<G::PublicState, G::PrivateState>::from_bytes(bytes)
+                        .expect("Invalid prior checkpoint");
+                // Players from the checkpoint resume where they left off
+                let contexts = checkpoint
+                    .contexts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, context)| TurboActionContextInner::from_checkpoint(context, i))
+                    .collect::<Vec<_>>();
+                Self {
+                    public_state: checkpoint.public_state,
+                    private_state: checkpoint.private_state,
+                    player_metadata: checkpoint.players,
+                    contexts,
+                    system: TurboSystemContextInner::from_checkpoint(&checkpoint.system),
+                }
+            }
+            None => {
+                let (public_state, private_state) = G::init(config, &initial_player_metadata);
+                let contexts = initial_player_metadata
+                    .iter()
+                    .enumerate()
+                    .map(|(i, metadata)| TurboActionContextInner::new(server_metadata, metadata, i))
+                    .collect::<Vec<_>>();
+                Self {
+                    public_state,
+                    private_state,
+                    player_metadata: initial_player_metadata,
+                    contexts,
+                    system: TurboSystemContextInner::new(server_metadata),
+                }
+            }
+        }
+    }
+
+    /// Hash of the checkpoint of the current state, committed as `stateHash`.
+    fn state_hash(&self) -> [u8; 32] {
+        let checkpoint = TurboCheckpoint {
+            public_state: &self.public_state,
+            private_state: &self.private_state,
+            players: self.player_metadata.clone(),
+            contexts: self.contexts.iter().map(|c| c.checkpoint()).collect(),
+            system: self.system.checkpoint(),
+        };
+        checkpoint_hash(&checkpoint.to_bytes())
+    }
+}
+
+// `on_state` sees the state before every action and once more after the last one
+fn turbo_program_inner<G: TurboGame>(
+    state: &mut TurboProgramState<G>,
     action_raw: &[u8],
-    contexts: &mut [&mut TurboActionContext],
-) -> Vec<u8>
-where
-    PublicState: Default + SolValue,
-    PrivateState: Default,
-    GameAction: TurboActionSerialization,
-{
-    let mut public_state = PublicState::default();
-    let mut private_state = PrivateState::default();
-    let mut remaining_actions = action_raw;
+    config: &G::Config,
+    server_metadata: &ServerMetadata,
+    mut on_state: impl FnMut(&TurboProgramState<G>),
+) {
+    let mut remaining_actions = decode_actions(action_raw).unwrap_or_else(|e| panic!("{}", e));
+    let actions_len = remaining_actions.len();
 
     while !remaining_actions.is_empty() {
-        let player_idx = remaining_actions[0] as usize;
+        on_state(state);
+        let TurboProgramState {
+            public_state,
+            private_state,
+            player_metadata,
+            contexts,
+            system,
+        } = &mut *state;
 
-        if player_idx >= 0x70 {
-            panic!("Invalid action type");
+        // The session closes at the terminal state, no action may follow it
+        if G::is_terminal(public_state, private_state) {
+            panic!("{}", TurboError::SessionFinished);
         }
 
-        let (action, next_actions) =
-            GameAction::deserialize(&remaining_actions[1..]).expect("Failed to deserialize action");
+        let offset = actions_len - remaining_actions.len();
+        let (sender, timestamp, action_bytes) =
+            decode_stamped(remaining_actions).unwrap_or_else(|e| panic!("{}", e.at_offset(offset)));
+        let action_offset = actions_len - action_bytes.len();
 
-        // Update action hash in the context
-        let context = &mut contexts[player_idx];
-        context.update_action_hash(
-            &remaining_actions[1..remaining_actions.len() - next_actions.len()],
-        );
+        // Actions are stamped by the server in order, an older timestamp is a forged transcript
+        if timestamp < system.timestamp() {
+            panic!("Timestamp before the previous action");
+        }
+
+        let player_idx = match sender {
+            ActionSender::Player(player_idx) => player_idx,
+            ActionSender::System(kind) => {
+                let next_actions = match kind {
+                    SYSTEM_GAME_ACTION => {
+                        let (action, next_actions) = G::SystemAction::deserialize(action_bytes)
+                            .unwrap_or_else(|e| panic!("{}", e.at_offset(action_offset)));
+
+                        let mut context = TurboSystemContext::new_from_inner(
+                            server_metadata,
+                            player_metadata,
+                            system.clone(),
+                        )
+                        .with_timestamp(timestamp)
+                        .with_config(config);
+                        context.update_action_hash(
+                            &remaining_actions[..remaining_actions.len() - next_actions.len()],
+                        );
+
+                        // A rejected action leaves the state and the system context untouched
+                        let mut next_public_state = public_state.clone();
+                        let mut next_private_state = private_state.clone();
+                        if G::reduce_system(
+                            &mut next_public_state,
+                            &mut next_private_state,
+                            &action,
+                            &mut context,
+                        )
+                        .is_ok()
+                        {
+                            *public_state = next_public_state;
+                            *private_state = next_private_state;
+                            context.inner.advance_tick();
+                            context.inner.advance(timestamp);
+                            *system = context.inner;
+                        }
+                        next_actions
+                    }
+                    SYSTEM_JOIN => {
+                        let (random_seed, key_bytes) = <[u32; 16]>::decode_field(action_bytes)
+                            .unwrap_or_else(|e| panic!("{}", e.at_offset(action_offset)));
+                        let (public_key, next_actions) =
+                            PlayerPublicKey::decode_optional(key_bytes).unwrap_or_else(|e| {
+                                panic!("{}", e.at_offset(actions_len - key_bytes.len()))
+                            });
+                        // `TurboSession` refuses keys nobody can sign for
+                        if let Some(public_key) = &public_key {
+                            public_key.validate().unwrap_or_else(|e| panic!("{}", e));
+                        }
+
+                        let player_idx = player_metadata.len();
+                        player_metadata.push(PlayerMetadata {
+                            random_seed,
+                            public_key,
+                        });
+                        let mut context = TurboActionContextInner::new(
+                            server_metadata,
+                            &player_metadata[player_idx],
+                            player_idx,
+                        );
+                        context.mark_joined(system.sequence());
+                        contexts.push(context);
+
+                        let mut context = TurboSystemContext::new_from_inner(
+                            server_metadata,
+                            player_metadata,
+                            system.clone(),
+                        )
+                        .with_timestamp(timestamp)
+                        .with_config(config);
+                        context.update_action_hash(
+                            &remaining_actions[..remaining_actions.len() - next_actions.len()],
+                        );
+                        G::on_join(public_state, private_state, player_idx, &mut context);
+                        context.inner.advance(timestamp);
+                        *system = context.inner;
+                        next_actions
+                    }
+                    SYSTEM_LEAVE => {
+                        let (player_idx, next_actions) = u32::decode_field(action_bytes)
+                            .unwrap_or_else(|e| panic!("{}", e.at_offset(action_offset)));
+                        let player_idx = player_idx as usize;
+
+                        let player = contexts
+                            .get_mut(player_idx)
+                            .unwrap_or_else(|| panic!("{}", TurboError::UnknownPlayer(player_idx)));
+                        if !player.is_active() {
+                            panic!("{}", TurboError::PlayerLeft(player_idx));
+                        }
+                        player.mark_left(system.sequence());
+
+                        let mut context = TurboSystemContext::new_from_inner(
+                            server_metadata,
+                            player_metadata,
+                            system.clone(),
+                        )
+                        .with_timestamp(timestamp)
+                        .with_config(config);
+                        context.update_action_hash(
+                            &remaining_actions[..remaining_actions.len() - next_actions.len()],
+                        );
+                        G::on_leave(public_state, private_state, player_idx, &mut context);
+                        context.inner.advance(timestamp);
+                        *system = context.inner;
+                        next_actions
+                    }
+                    SYSTEM_DEADLINE => {
+                        // The server cannot run the penalties early
+                        match G::deadline(public_state, private_state) {
+                            Some(deadline) if timestamp >= deadline => {}
+                            Some(_) => panic!("Deadline not reached"),
+                            None => panic!("No deadline pending"),
+                        }
+
+                        let mut context = TurboSystemContext::new_from_inner(
+                            server_metadata,
+                            player_metadata,
+                            system.clone(),
+                        )
+                        .with_timestamp(timestamp)
+                        .with_config(config);
+                        context.update_action_hash(
+                            &remaining_actions[..remaining_actions.len() - action_bytes.len()],
+                        );
+                        G::on_deadline(public_state, private_state, &mut context);
+                        context.inner.advance(timestamp);
+                        *system = context.inner;
+                        action_bytes
+                    }
+                    _ => panic!("Unknown system action"),
+                };
+
+                remaining_actions = next_actions;
+                continue;
+            }
+        };
 
-        // Process the action
-        reducer(&mut public_state, &mut private_state, &action, context);
+        let (action, mut next_actions) = G::Action::deserialize(action_bytes)
+            .unwrap_or_else(|e| panic!("{}", e.at_offset(action_offset)));
+        let action_bytes = &action_bytes[..action_bytes.len() - next_actions.len()];
+
+        let inner = contexts
+            .get(player_idx)
+            .unwrap_or_else(|| panic!("{}", TurboError::UnknownPlayer(player_idx)));
+        if !inner.is_active() {
+            panic!("{}", TurboError::PlayerLeft(player_idx));
+        }
+
+        // Players with a key sign every action, verified with the SP1 precompiles
+        if let Some(public_key) = &player_metadata[player_idx].public_key {
+            let (signature, rest) = split_signature(next_actions)
+                .unwrap_or_else(|e| panic!("{}", e.at_offset(actions_len - next_actions.len())));
+            let message = action_signing_message(
+                &server_metadata.seed_commitment(),
+                inner.action_sequence(),
+                action_bytes,
+            );
+            if !public_key.verify(&message, signature) {
+                panic!("{}", TurboError::InvalidSignature(player_idx));
+            }
+            next_actions = rest;
+        }
+
+        // The action hash covers the timestamp, so the prover cannot move an action in time
+        let mut context = TurboActionContext::new_from_inner(
+            server_metadata,
+            &player_metadata[player_idx],
+            inner.clone(),
+        )
+        .with_timestamp(timestamp)
+        .with_config(config);
+        context.update_action_hash(&action_hash_input(timestamp, action_bytes));
+
+        // Process the action, a rejected action leaves the context untouched
+        let mut next_public_state = public_state.clone();
+        let mut next_private_state = private_state.clone();
+        if G::reduce(
+            &mut next_public_state,
+            &mut next_private_state,
+            &action,
+            &mut context,
+        )
+        .is_ok()
+        {
+            *public_state = next_public_state;
+            *private_state = next_private_state;
+            // Only accepted actions use up a signature sequence number, as in `TurboSession`
+            context.inner.advance_action_sequence();
+            contexts[player_idx] = context.inner;
+            system.advance(timestamp);
+        }
 
         // Move to next action
         remaining_actions = next_actions;
     }
-
-    PublicState::abi_encode(&public_state)
+    on_state(state);
 }
 
-pub fn turbo_program<PublicState, PrivateState, GameAction>(
-    reducer: TurboReducer<PublicState, PrivateState, GameAction>,
-) where
-    PublicState: Default + SolValue,
-    PrivateState: Default,
-    GameAction: TurboActionSerialization,
-{
+pub fn turbo_program<G: TurboGame>() {
     let server_metadata = sp1_zkvm::io::read::<ServerMetadata>();
-    let player_metadata = sp1_zkvm::io::read::<Vec<PlayerMetadata>>();
+    let config = sp1_zkvm::io::read::<G::Config>();
+    let initial_player_metadata = sp1_zkvm::io::read::<Vec<PlayerMetadata>>();
+    let prior_checkpoint = sp1_zkvm::io::read::<Option<Vec<u8>>>();
     let action_raw = sp1_zkvm::io::read::<Vec<u8>>();
+    let view_secret = sp1_zkvm::io::read::<[u8; 32]>();
 
-    // Create contexts for all players and set them
-    let mut player_contexts = Vec::new();
-    let mut context_refs = Vec::new();
-
-    // First create all the contexts
-    for (i, metadata) in player_metadata.iter().enumerate() {
-        player_contexts.push(TurboActionContext::new(&server_metadata, metadata, i));
-    }
-
-    // Then collect mutable references to them
-    for context in &mut player_contexts {
-        context_refs.push(context);
+    if sp1_zkvm::io::read::<bool>() {
+        let trace = trace_turbo_program::<G>(
+            &server_metadata,
+            &config,
+            initial_player_metadata,
+            prior_checkpoint,
+            &action_raw,
+        );
+        sp1_zkvm::io::commit_slice(&trace.concat());
+        return;
     }
 
-    // Encode and commit the final public state
-    sp1_zkvm::io::commit_slice(&turbo_program_inner(
-        reducer,
+    let public_values = run_turbo_program::<G>(
+        &server_metadata,
+        &config,
+        initial_player_metadata,
+        prior_checkpoint,
         &action_raw,
-        &mut context_refs,
-    ));
+        &view_secret,
+    );
+    sp1_zkvm::io::commit_slice(&public_values.abi_encode());
+}
+
+/// What `turbo_program` commits for the given stdin values, computed outside the zkVM as well,
+/// e.g. to check a game against `TurboSession` without executing the program. Panics wherever the
+/// program fails.
+pub fn run_turbo_program<G: TurboGame>(
+    server_metadata: &ServerMetadata,
+    config: &G::Config,
+    initial_player_metadata: Vec<PlayerMetadata>,
+    prior_checkpoint: Option<Vec<u8>>,
+    action_raw: &[u8],
+    view_secret: &[u8; 32],
+) -> TurboPublicValues {
+    let mut state = TurboProgramState::<G>::new(
+        server_metadata,
+        config,
+        initial_player_metadata,
+        prior_checkpoint.as_deref(),
+    );
+    let prior_state_hash = prior_checkpoint
+        .as_ref()
+        .map_or([0u8; 32], |bytes| checkpoint_hash(bytes));
+
+    turbo_program_inner(&mut state, action_raw, config, server_metadata, |_| {});
+    let state_hash = state.state_hash();
+    let TurboProgramState {
+        public_state,
+        private_state,
+        player_metadata,
+        contexts,
+        system,
+    } = state;
+    let state = G::Output::from_state(&public_state, &private_state).abi_encode();
+
+    // Views are encrypted with keys derived from the state hash, so they follow the checkpoint
+    let player_views = if G::ENCRYPTED_VIEWS {
+        encrypted_player_views::<G>(
+            &public_state,
+            &private_state,
+            view_secret,
+            &player_metadata,
+            &state_hash,
+        )
+        .unwrap_or_else(|e| panic!("{}", e))
+    } else {
+        Vec::new()
+    };
+
+    // The final public state together with the session transcript
+    TurboPublicValues::new(
+        state,
+        prior_state_hash,
+        state_hash,
+        server_metadata,
+        &player_metadata,
+        contexts.iter().map(|c| c.action_hash()).collect(),
+        system.action_hash(),
+    )
+    .with_config_hash(config_hash(config))
+    .with_finished(G::is_terminal(&public_state, &private_state))
+    .with_private_state_commitment(commit_private_state(
+        &private_state_bytes(&private_state),
+        &private_state_salt(server_metadata),
+    ))
+    .with_presence(
+        contexts.iter().map(|c| c.joined_at()).collect(),
+        contexts.iter().map(|c| c.left_at()).collect(),
+    )
+    .with_player_views(player_views)
+}
+
+/// `stateHash` before the first action and after every action, as `TurboSession::state_hash`
+/// reports it along the way. Rejected actions count as actions, leaving the hash unchanged.
+pub fn trace_turbo_program<G: TurboGame>(
+    server_metadata: &ServerMetadata,
+    config: &G::Config,
+    initial_player_metadata: Vec<PlayerMetadata>,
+    prior_checkpoint: Option<Vec<u8>>,
+    action_raw: &[u8],
+) -> Vec<[u8; 32]> {
+    let mut state = TurboProgramState::<G>::new(
+        server_metadata,
+        config,
+        initial_player_metadata,
+        prior_checkpoint.as_deref(),
+    );
+    let mut trace = Vec::new();
+    turbo_program_inner(&mut state, action_raw, config, server_metadata, |state| {
+        trace.push(state.state_hash())
+    });
+    trace
 }
@@ -1,8 +1,13 @@
+use std::ops::Range;
+
+use rand_core::RngCore;
+#[cfg(target_os = "zkvm")]
 use sp1_lib::{syscall_bn254_add, syscall_bn254_double};
 use substrate_bn::*;
 
 use crate::crypto::bn_math::{bn254_add, bn254_double};
 use crate::crypto::bn_serialize::{bn254_export_affine_g1_memcpy, bn254_import_affine_g1_memcpy};
+use crate::crypto::keccak::keccak256;
 use crate::rand::pcg::{rxs_m_xs, xsh_rs};
 
 #[derive(Clone)]
@@ -78,8 +83,58 @@ impl BnRandomizer {
         }
     }
 
+    /// Independent stream derived from `seed` and `label`.
+    ///
+    /// The stream seed is the seed point multiplied by `2^248` plus 248 bits of the Keccak-256 of
+    /// the label. Starting the double-and-add at the implicit top bit keeps every intermediate
+    /// multiple below the group order, so the additions never see equal points.
+    pub fn new_with_label(seed: &[u32; 16], label: &str) -> Self {
+        let scalar = keccak256(label.as_bytes());
+
+        #[cfg(target_os = "zkvm")]
+        let mut point: [u32; 16] = *seed;
+
+        #[cfg(not(target_os = "zkvm"))]
+        let base = bn254_import_affine_g1_memcpy(seed);
+        #[cfg(not(target_os = "zkvm"))]
+        let mut point = base;
+
+        for bit in (0..248).rev() {
+            let is_set = (scalar[bit / 8] >> (bit % 8)) & 1 == 1;
+
+            #[cfg(target_os = "zkvm")]
+            unsafe {
+                syscall_bn254_double(&mut point);
+                if is_set {
+                    syscall_bn254_add(&mut point, seed);
+                }
+            }
+
+            #[cfg(not(target_os = "zkvm"))]
+            {
+                point = bn254_double(point);
+                if is_set {
+                    point = bn254_add(point, base);
+                }
+            }
+        }
+
+        #[cfg(not(target_os = "zkvm"))]
+        let point = bn254_export_affine_g1_memcpy(&point);
+
+        Self::new_with_seed(&point)
+    }
+
+    /// Restore a randomizer from a seed returned by `current_seed` and its nonce.
+    pub fn from_parts(current: [u32; 16], nonce: u64) -> Self {
+        Self {
+            nonce,
+            ..Self::new_with_seed(&current)
+        }
+    }
+
     fn next_rand(&mut self) {
-        if self.nonce % 2 == 0 {
+        if self.nonce & 1 == 0 {
             #[cfg(target_os = "zkvm")]
             unsafe {
                 syscall_bn254_double(&mut self.current);
@@ -114,7 +169,123 @@ impl BnRandomizer {
         }
     }
 
+    /// Uniform value in `0..bound` without modulo bias (Lemire's multiply and reject).
+    pub fn next_below(&mut self, bound: u32) -> u32 {
+        assert!(bound > 0, "bound must be positive");
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let m = self.next_u32() as u64 * bound as u64;
+            if m as u32 >= threshold {
+                return (m >> 32) as u32;
+            }
+        }
+    }
+
+    /// Uniform value in `0..bound` without modulo bias.
+    pub fn next_below_u64(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "bound must be positive");
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let m = self.next_u64() as u128 * bound as u128;
+            if m as u64 >= threshold {
+                return (m >> 64) as u64;
+            }
+        }
+    }
+
+    /// Uniform value in `range`, panics if the range is empty.
+    pub fn next_range(&mut self, range: Range<u32>) -> u32 {
+        assert!(range.start < range.end, "range must not be empty");
+        range.start + self.next_below(range.end - range.start)
+    }
+
+    /// `true` with probability `p`, clamped to `0.0..=1.0`.
+    ///
+    /// `p` is converted to a 64-bit fixed point threshold, the comparison itself is integer only.
+    pub fn next_bool(&mut self, p: f64) -> bool {
+        if p >= 1.0 {
+            return true;
+        }
+        if p.is_nan() || p <= 0.0 {
+            return false;
+        }
+        let threshold = (p * 18_446_744_073_709_551_616.0) as u64;
+        self.next_u64() < threshold
+    }
+
+    /// Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.next_below_u64(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        Some(&items[self.next_below_u64(items.len() as u64) as usize])
+    }
+
+    /// Index picked with probability proportional to its weight, `None` if all weights are zero.
+    pub fn weighted_index(&mut self, weights: &[u32]) -> Option<usize> {
+        let total: u64 = weights.iter().map(|w| *w as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut target = self.next_below_u64(total);
+        for (i, weight) in weights.iter().enumerate() {
+            if target < *weight as u64 {
+                return Some(i);
+            }
+            target -= *weight as u64;
+        }
+        unreachable!()
+    }
+
+    /// Item picked with probability proportional to the weight at the same index.
+    pub fn choose_weighted<'a, T>(&mut self, items: &'a [T], weights: &[u32]) -> Option<&'a T> {
+        assert_eq!(items.len(), weights.len(), "one weight per item");
+        self.weighted_index(weights).map(|i| &items[i])
+    }
+
+    /// Fill `dest` with random bytes, 4 bytes per `next_u32` in little-endian order.
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
     pub fn current_seed(&self) -> [u32; 16] {
         self.current
     }
+
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+}
+
+/// Lets `rand` distributions and `rand::seq` run on the game randomizer.
+///
+/// Every method forwards to the inherent one of the same name, so the results are the same on
+/// host and zkVM and the same as calling `BnRandomizer` directly.
+impl RngCore for BnRandomizer {
+    fn next_u32(&mut self) -> u32 {
+        BnRandomizer::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        BnRandomizer::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        BnRandomizer::fill_bytes(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        BnRandomizer::fill_bytes(self, dest);
+        Ok(())
+    }
 }
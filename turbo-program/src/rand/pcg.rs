@@ -19,7 +19,7 @@ pub fn xsh_rs(mut x: u64) -> u32 {
 ///
 /// This function does the following:
 /// 1. Extracts the top 6 bits as the rotate count from the high part:
-///      count = high >> 58;  // (Because 128-122 = 6, and the top 6 bits are in `high`)
+///    count = high >> 58;  // (Because 128-122 = 6, and the top 6 bits are in `high`)
 /// 2. Combines the two parts by taking the XOR of the high and low parts.
 ///    This mimics the operation: (x ^ (x >> 64)) as u64, where x = (high<<64) | low.
 ///    When computed, this yields (low ^ high).
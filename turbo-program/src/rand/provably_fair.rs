@@ -1 +1,49 @@
+//! Commit-reveal of the server seed.
+//!
+//! The server commits to its seed when a session is created, before any player seed is known,
+//! and reveals it once the session is over. Anyone holding the commitment (it is also part of
+//! `TurboPublicValues`) can then check the reveal, so the server could not have picked its seed
+//! after seeing the players' seeds or moves.
 
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    crypto::keccak::keccak256,
+    metadata::{seed_to_bytes, ServerMetadata},
+};
+
+/// Keccak-256 of the little-endian seed bytes.
+pub fn commit_server_seed(seed: &[u32; 16]) -> [u8; 32] {
+    keccak256(&seed_to_bytes(seed))
+}
+
+/// Check a revealed server seed against its commitment.
+pub fn verify_server_seed(seed: &[u32; 16], commitment: &[u8; 32]) -> bool {
+    commit_server_seed(seed) == *commitment
+}
+
+/// A revealed server seed together with the commitment published at session creation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerSeedReveal {
+    pub seed: [u32; 16],
+    pub commitment: [u8; 32],
+}
+
+impl ServerSeedReveal {
+    pub fn new(server_metadata: &ServerMetadata) -> Self {
+        Self {
+            seed: server_metadata.random_seed,
+            commitment: server_metadata.seed_commitment(),
+        }
+    }
+
+    pub fn verify(&self) -> bool {
+        verify_server_seed(&self.seed, &self.commitment)
+    }
+
+    pub fn server_metadata(&self) -> ServerMetadata {
+        ServerMetadata {
+            random_seed: self.seed,
+        }
+    }
+}
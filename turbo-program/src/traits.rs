@@ -1,4 +1,127 @@
+use serde_json::Value;
+
+use crate::error::TurboError;
+
+pub use turbo_program_derive::TurboAction;
+
 pub trait TurboActionSerialization: Sized {
-    fn deserialize(action: &[u8]) -> Result<(Self, &[u8]), &'static str>;
-    fn serialize_json(json_str: &str) -> Result<Vec<u8>, &'static str>;
+    fn deserialize(action: &[u8]) -> Result<(Self, &[u8]), TurboError>;
+    fn serialize(&self) -> Vec<u8>;
+    fn serialize_json(json_str: &str) -> Result<Vec<u8>, TurboError>;
+}
+
+/// A single field of a `#[derive(TurboAction)]` variant.
+///
+/// Decode errors report offsets relative to the start of the field.
+///
+/// Integers are encoded big-endian, `bool` as one byte and arrays element by element.
+pub trait TurboActionField: Sized {
+    fn decode_field(data: &[u8]) -> Result<(Self, &[u8]), TurboError>;
+    fn encode_field(&self, out: &mut Vec<u8>);
+    fn from_json(value: &Value) -> Result<Self, TurboError>;
+}
+
+/// Split a `{"action": ..., "data": [...]}` object into its action name and data array.
+///
+/// `data` may be omitted for actions without fields.
+pub fn parse_action_json(json_str: &str) -> Result<(String, Vec<Value>), TurboError> {
+    let action: Value =
+        serde_json::from_str(json_str).map_err(|_| TurboError::InvalidAction("Invalid JSON"))?;
+    let action_type = action["action"]
+        .as_str()
+        .ok_or(TurboError::InvalidAction("Missing action field"))?
+        .to_string();
+    let data = match &action["data"] {
+        Value::Null => Vec::new(),
+        Value::Array(data) => data.clone(),
+        _ => return Err(TurboError::InvalidAction("Missing data field")),
+    };
+
+    Ok((action_type, data))
+}
+
+macro_rules! impl_int_field {
+    ($as_json:ident => $($ty:ty),*) => {
+        $(
+            impl TurboActionField for $ty {
+                fn decode_field(data: &[u8]) -> Result<(Self, &[u8]), TurboError> {
+                    const SIZE: usize = std::mem::size_of::<$ty>();
+                    if data.len() < SIZE {
+                        return Err(TurboError::decode("Unexpected end of action"));
+                    }
+                    let (bytes, rest) = data.split_at(SIZE);
+                    Ok((<$ty>::from_be_bytes(bytes.try_into().unwrap()), rest))
+                }
+
+                fn encode_field(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_be_bytes());
+                }
+
+                fn from_json(value: &Value) -> Result<Self, TurboError> {
+                    value
+                        .$as_json()
+                        .and_then(|n| <$ty>::try_from(n).ok())
+                        .ok_or(TurboError::InvalidAction("Invalid integer field"))
+                }
+            }
+        )*
+    };
+}
+
+impl_int_field!(as_u64 => u8, u16, u32, u64);
+impl_int_field!(as_i64 => i8, i16, i32, i64);
+
+impl TurboActionField for bool {
+    fn decode_field(data: &[u8]) -> Result<(Self, &[u8]), TurboError> {
+        match data.split_first() {
+            Some((0, rest)) => Ok((false, rest)),
+            Some((1, rest)) => Ok((true, rest)),
+            Some(_) => Err(TurboError::decode("Invalid bool field")),
+            None => Err(TurboError::decode("Unexpected end of action")),
+        }
+    }
+
+    fn encode_field(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn from_json(value: &Value) -> Result<Self, TurboError> {
+        value
+            .as_bool()
+            .ok_or(TurboError::InvalidAction("Invalid bool field"))
+    }
+}
+
+impl<T: TurboActionField, const N: usize> TurboActionField for [T; N] {
+    fn decode_field(data: &[u8]) -> Result<(Self, &[u8]), TurboError> {
+        let mut items = Vec::with_capacity(N);
+        let mut rest = data;
+        for _ in 0..N {
+            let (item, next) =
+                T::decode_field(rest).map_err(|e| e.at_offset(data.len() - rest.len()))?;
+            items.push(item);
+            rest = next;
+        }
+        Ok((items.try_into().ok().unwrap(), rest))
+    }
+
+    fn encode_field(&self, out: &mut Vec<u8>) {
+        for item in self {
+            item.encode_field(out);
+        }
+    }
+
+    fn from_json(value: &Value) -> Result<Self, TurboError> {
+        let values = value
+            .as_array()
+            .ok_or(TurboError::InvalidAction("Invalid array field"))?;
+        if values.len() != N {
+            return Err(TurboError::InvalidAction("Invalid array field length"));
+        }
+        let items = values
+            .iter()
+            .map(T::from_json)
+            .collect::<Result<Vec<T>, _>>()?;
+        Ok(items.try_into().ok().unwrap())
+    }
 }
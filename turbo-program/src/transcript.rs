@@ -0,0 +1,133 @@
+use alloy_sol_types::sol;
+use serde::Serialize;
+
+use crate::{
+    crypto::{keccak::keccak256, signature::PlayerPublicKey},
+    metadata::{PlayerMetadata, ServerMetadata},
+};
+
+sol! {
+    /// Public values committed by `turbo_program`.
+    ///
+    /// Binds the ABI-encoded final state to the session transcript that produced it.
+    #[derive(Debug)]
+    struct TurboPublicValues {
+        /// ABI-encoded `TurboGame::Output` of the final state.
+        bytes state;
+        /// Checkpoint the proof continued from, zero for a fresh session.
+        bytes32 priorStateHash;
+        /// Checkpoint the proof ends at, so segment proofs can be chained.
+        bytes32 stateHash;
+        /// Commitment to the server seed, published when the session is created.
+        bytes32 serverSeedCommitment;
+        /// Hash of the `TurboGame::Config` the session was created with, see `config_hash`, so
+        /// results of different game modes can be told apart.
+        bytes32 configHash;
+        /// Set when the game reached its terminal state, no action can follow it.
+        bool finished;
+        /// Salted hash of the final private state, see `private_state`, checked against a reveal
+        /// once the game is over.
+        bytes32 privateStateCommitment;
+        uint32 playerCount;
+        /// Random seed of every player that joined, in join order.
+        uint32[16][] playerSeeds;
+        /// Every player's final action hash.
+        uint32[8][] actionHashes;
+        /// Hash of the system actions dispatched by the server.
+        uint32[8] systemActionHash;
+        /// Session sequence numbers (accepted actions of any kind) at which each player joined.
+        uint64[] playerJoinedAt;
+        /// Sequence numbers at which each player left, `type(uint64).max` for players still
+        /// present.
+        uint64[] playerLeftAt;
+        /// Key each player signed its actions with (tag byte and key bytes), empty for players
+        /// without a key.
+        bytes[] playerPublicKeys;
+        /// Every player's `TurboGame::PlayerView` encrypted to the player's key when the game sets
+        /// `ENCRYPTED_VIEWS` (see `view`), empty otherwise.
+        bytes[] playerViews;
+    }
+
+    /// Public values committed by the aggregation program.
+    ///
+    /// `session` covers every aggregated segment, from the first segment's `priorStateHash` to the
+    /// last segment's `stateHash`. `segmentVkey` is the digest of the program the segments were
+    /// proven with and must be checked by the verifier.
+    #[derive(Debug)]
+    struct TurboAggregatedPublicValues {
+        uint32[8] segmentVkey;
+        uint32 segmentCount;
+        TurboPublicValues session;
+    }
+}
+
+impl TurboPublicValues {
+    pub fn new(
+        state: Vec<u8>,
+        prior_state_hash: [u8; 32],
+        state_hash: [u8; 32],
+        server_metadata: &ServerMetadata,
+        player_metadata: &[PlayerMetadata],
+        action_hashes: Vec<[u32; 8]>,
+        system_action_hash: [u32; 8],
+    ) -> Self {
+        Self {
+            state: state.into(),
+            priorStateHash: prior_state_hash.into(),
+            stateHash: state_hash.into(),
+            serverSeedCommitment: server_metadata.seed_commitment().into(),
+            configHash: [0u8; 32].into(),
+            finished: false,
+            privateStateCommitment: [0u8; 32].into(),
+            playerCount: player_metadata.len() as u32,
+            playerSeeds: player_metadata.iter().map(|p| p.random_seed).collect(),
+            actionHashes: action_hashes,
+            systemActionHash: system_action_hash,
+            playerJoinedAt: vec![0; player_metadata.len()],
+            playerLeftAt: vec![u64::MAX; player_metadata.len()],
+            playerPublicKeys: player_metadata
+                .iter()
+                .map(|p| PlayerPublicKey::commitment(p.public_key.as_ref()).into())
+                .collect(),
+            playerViews: Vec::new(),
+        }
+    }
+
+    /// Set the hash of the game configuration, zero by default.
+    pub fn with_config_hash(mut self, config_hash: [u8; 32]) -> Self {
+        self.configHash = config_hash.into();
+        self
+    }
+
+    pub fn with_finished(mut self, finished: bool) -> Self {
+        self.finished = finished;
+        self
+    }
+
+    /// Set the commitment to the private state, zero by default.
+    pub fn with_private_state_commitment(mut self, commitment: [u8; 32]) -> Self {
+        self.privateStateCommitment = commitment.into();
+        self
+    }
+
+    pub fn with_player_views(mut self, views: Vec<Vec<u8>>) -> Self {
+        self.playerViews = views.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set when each player joined and left, by default every player is present from the start.
+    pub fn with_presence(mut self, joined_at: Vec<u64>, left_at: Vec<Option<u64>>) -> Self {
+        self.playerJoinedAt = joined_at;
+        self.playerLeftAt = left_at
+            .into_iter()
+            .map(|left_at| left_at.unwrap_or(u64::MAX))
+            .collect();
+        self
+    }
+}
+
+/// Hash committed for a game configuration, computed over its bincode serialization like
+/// `checkpoint_hash`.
+pub fn config_hash<C: Serialize>(config: &C) -> [u8; 32] {
+    keccak256(&bincode::serialize(config).expect("Failed to serialize config"))
+}
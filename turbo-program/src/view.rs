@@ -0,0 +1,186 @@
+//! Player views encrypted to the players' keys.
+//!
+//! With `TurboGame::ENCRYPTED_VIEWS` the program commits, for every player with a public key, the
+//! JSON of `TurboGame::PlayerView` encrypted to that key. A player decrypts its own view and can
+//! show the proof to anyone to attest to what it was shown, without revealing the other players'
+//! views.
+//!
+//! The encryption is an ECIES: Diffie-Hellman between an ephemeral key and the player's key
+//! (secp256k1, or X25519 on the Montgomery form of an Ed25519 key), a Keccak-256 keystream and a
+//! Keccak-256 tag. The ephemeral key is derived from the view secret, the state hash and the
+//! player index so the zkVM and the session produce identical bytes, and a keystream is never
+//! reused for two different states.
+//!
+//! The view secret is private randomness the server passes to the program and never reveals.
+//! The server seed cannot take its place: it is revealed after the game, and with it anyone could
+//! recompute the ephemeral keys from the public values and decrypt every view.
+
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+
+use crate::{
+    crypto::{keccak::keccak256, signature::PlayerPublicKey},
+    error::TurboError,
+    game::{TurboGame, TurboPlayerView},
+    metadata::PlayerMetadata,
+};
+
+const EPHEMERAL_LABEL: &[u8] = b"turbo:view";
+const KEYSTREAM_LABEL: &[u8] = b"turbo:view:keystream";
+const TAG_LABEL: &[u8] = b"turbo:view:tag";
+const TAG_LEN: usize = 32;
+
+/// Secret key a player decrypts its view with, the counterpart of `PlayerPublicKey`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayerSecretKey {
+    /// Ed25519 signing key seed.
+    Ed25519([u8; 32]),
+    Secp256k1([u8; 32]),
+}
+
+/// Views committed in `TurboPublicValues::playerViews`, empty for players without a key.
+///
+/// Fails if a player's key is not a valid point. `TurboSession` refuses such keys at join, so
+/// this is never expected; an empty view would read as a player without a key.
+pub fn encrypted_player_views<G: TurboGame>(
+    public_state: &G::PublicState,
+    private_state: &G::PrivateState,
+    view_secret: &[u8; 32],
+    player_metadata: &[PlayerMetadata],
+    state_hash: &[u8; 32],
+) -> Result<Vec<Vec<u8>>, TurboError> {
+    player_metadata
+        .iter()
+        .enumerate()
+        .map(|(player_idx, metadata)| match &metadata.public_key {
+            Some(public_key) => {
+                let view = G::PlayerView::for_player(public_state, private_state, player_idx);
+                let plaintext = serde_json::to_vec(&view).expect("Failed to serialize view");
+                let seed = ephemeral_seed(view_secret, state_hash, player_idx);
+                encrypt_view(public_key, &seed, &plaintext)
+                    .ok_or(TurboError::InvalidAction("Invalid public key"))
+            }
+            None => Ok(Vec::new()),
+        })
+        .collect()
+}
+
+fn ephemeral_seed(view_secret: &[u8; 32], state_hash: &[u8; 32], player_idx: usize) -> [u8; 32] {
+    keccak256(
+        &[
+            EPHEMERAL_LABEL,
+            view_secret,
+            state_hash,
+            &(player_idx as u32).to_be_bytes(),
+        ]
+        .concat(),
+    )
+}
+
+/// Encrypt `plaintext` to `public_key`: the ephemeral public key, the ciphertext and the tag.
+///
+/// `None` if the public key is not a valid point.
+pub fn encrypt_view(
+    public_key: &PlayerPublicKey,
+    ephemeral_seed: &[u8; 32],
+    plaintext: &[u8],
+) -> Option<Vec<u8>> {
+    let (ephemeral_public, shared_secret) = match public_key {
+        PlayerPublicKey::Ed25519(key) => {
+            let recipient = ed25519_dalek::VerifyingKey::from_bytes(key).ok()?;
+            let ephemeral = ed25519_dalek::SigningKey::from_bytes(ephemeral_seed);
+            let shared_secret = recipient
+                .to_montgomery()
+                .mul_clamped(ephemeral.to_scalar_bytes());
+            (
+                ephemeral.verifying_key().to_bytes().to_vec(),
+                shared_secret.to_bytes(),
+            )
+        }
+        PlayerPublicKey::Secp256k1(key) => {
+            let recipient = k256::PublicKey::from_sec1_bytes(key).ok()?;
+            let ephemeral = secp256k1_secret(ephemeral_seed);
+            let shared_secret =
+                k256::ecdh::diffie_hellman(ephemeral.to_nonzero_scalar(), recipient.as_affine());
+            (
+                ephemeral
+                    .public_key()
+                    .to_encoded_point(true)
+                    .as_bytes()
+                    .to_vec(),
+                (*shared_secret.raw_secret_bytes()).into(),
+            )
+        }
+    };
+
+    let mut ciphertext = plaintext.to_vec();
+    apply_keystream(&shared_secret, &mut ciphertext);
+    let tag = tag(&shared_secret, &ciphertext);
+    Some([ephemeral_public, ciphertext, tag.to_vec()].concat())
+}
+
+/// Decrypt a view encrypted with `encrypt_view`, `None` if it was not encrypted to `secret_key`.
+pub fn decrypt_view(secret_key: &PlayerSecretKey, encrypted: &[u8]) -> Option<Vec<u8>> {
+    let (shared_secret, body) = match secret_key {
+        PlayerSecretKey::Ed25519(seed) => {
+            let (ephemeral, body) = split_checked(encrypted, 32)?;
+            let ephemeral =
+                ed25519_dalek::VerifyingKey::from_bytes(ephemeral.try_into().ok()?).ok()?;
+            let secret = ed25519_dalek::SigningKey::from_bytes(seed);
+            let shared_secret = ephemeral
+                .to_montgomery()
+                .mul_clamped(secret.to_scalar_bytes());
+            (shared_secret.to_bytes(), body)
+        }
+        PlayerSecretKey::Secp256k1(secret) => {
+            let (ephemeral, body) = split_checked(encrypted, 33)?;
+            let ephemeral = k256::PublicKey::from_sec1_bytes(ephemeral).ok()?;
+            let secret = k256::SecretKey::from_slice(secret).ok()?;
+            let shared_secret =
+                k256::ecdh::diffie_hellman(secret.to_nonzero_scalar(), ephemeral.as_affine());
+            ((*shared_secret.raw_secret_bytes()).into(), body)
+        }
+    };
+
+    let (ciphertext, expected_tag) = split_checked(body, body.len().checked_sub(TAG_LEN)?)?;
+    if tag(&shared_secret, ciphertext) != expected_tag {
+        return None;
+    }
+    let mut plaintext = ciphertext.to_vec();
+    apply_keystream(&shared_secret, &mut plaintext);
+    Some(plaintext)
+}
+
+fn split_checked(data: &[u8], mid: usize) -> Option<(&[u8], &[u8])> {
+    (data.len() >= mid).then(|| data.split_at(mid))
+}
+
+// Hash the seed again until it is a valid scalar, a single hash almost always is
+fn secp256k1_secret(seed: &[u8; 32]) -> k256::SecretKey {
+    let mut candidate = *seed;
+    loop {
+        if let Ok(secret) = k256::SecretKey::from_slice(&candidate) {
+            return secret;
+        }
+        candidate = keccak256(&candidate);
+    }
+}
+
+fn apply_keystream(shared_secret: &[u8; 32], data: &mut [u8]) {
+    for (counter, chunk) in data.chunks_mut(32).enumerate() {
+        let block = keccak256(
+            &[
+                KEYSTREAM_LABEL,
+                shared_secret,
+                &(counter as u32).to_be_bytes(),
+            ]
+            .concat(),
+        );
+        for (byte, key) in chunk.iter_mut().zip(block) {
+            *byte ^= key;
+        }
+    }
+}
+
+fn tag(shared_secret: &[u8; 32], ciphertext: &[u8]) -> [u8; 32] {
+    keccak256(&[TAG_LABEL, shared_secret, ciphertext].concat())
+}
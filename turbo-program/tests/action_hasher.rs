@@ -0,0 +1,68 @@
+use turbo_program::crypto::{
+    action_hasher::{ActionHasher, KeccakHasher, Sha256Hasher},
+    fnv::FnvHasher,
+    keccak::keccak256,
+};
+
+fn words(hex: &str) -> [u32; 8] {
+    let mut words = [0u32; 8];
+    for (i, word) in words.iter_mut().enumerate() {
+        *word = u32::from_str_radix(&hex[i * 8..i * 8 + 8], 16).unwrap();
+    }
+    words
+}
+
+fn round_trip<H: ActionHasher>() {
+    let mut hasher = H::new();
+    hasher.update(b"seed");
+    hasher.update(&[1, 2, 3]);
+
+    let mut restored = H::from_parts(hasher.digest(), hasher.shift());
+    hasher.update(&[4, 5]);
+    restored.update(&[4, 5]);
+    assert_eq!(hasher.digest(), restored.digest());
+}
+
+#[test]
+fn chains_start_from_zero() {
+    // keccak256(bytes32(0)) and sha256(bytes32(0))
+    let mut keccak = KeccakHasher::new();
+    keccak.update(&[]);
+    assert_eq!(
+        keccak.digest(),
+        words("290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563")
+    );
+
+    let mut sha256 = Sha256Hasher::new();
+    sha256.update(&[]);
+    assert_eq!(
+        sha256.digest(),
+        words("66687aadf862bd776c8fc18b8e9f8e20089714856ee233b3902a591d0d5f2925")
+    );
+}
+
+#[test]
+fn keccak_chain_matches_abi_encode_packed() {
+    let mut hasher = KeccakHasher::new();
+    hasher.update(&[7]);
+    hasher.update(&[8, 9]);
+
+    let first = keccak256(&[[0u8; 32].as_slice(), &[7]].concat());
+    let second = keccak256(&[first.as_slice(), &[8, 9]].concat());
+    assert_eq!(
+        hasher.digest(),
+        words(
+            &second
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        )
+    );
+}
+
+#[test]
+fn hashers_restore_from_parts() {
+    round_trip::<FnvHasher>();
+    round_trip::<KeccakHasher>();
+    round_trip::<Sha256Hasher>();
+}
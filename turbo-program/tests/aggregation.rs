@@ -0,0 +1,71 @@
+use turbo_program::{
+    aggregation::aggregate_segments,
+    error::TurboError,
+    metadata::{PlayerMetadata, ServerMetadata},
+    transcript::{config_hash, TurboPublicValues},
+};
+
+fn segment(prior: u8, next: u8, players: &[PlayerMetadata]) -> TurboPublicValues {
+    TurboPublicValues::new(
+        vec![next],
+        [prior; 32],
+        [next; 32],
+        &ServerMetadata {
+            random_seed: [7; 16],
+        },
+        players,
+        vec![[next as u32; 8]; players.len()],
+        [next as u32; 8],
+    )
+}
+
+fn player(seed: u32) -> PlayerMetadata {
+    PlayerMetadata::new([seed; 16])
+}
+
+#[test]
+fn chained_segments_fold_into_one_session() {
+    let segments = [
+        segment(0, 1, &[player(1)]),
+        segment(1, 2, &[player(1), player(2)]),
+        segment(2, 3, &[player(1), player(2)]),
+    ];
+
+    let session = aggregate_segments(&segments).unwrap();
+    assert_eq!(session.priorStateHash, [0u8; 32]);
+    assert_eq!(session.stateHash, [3u8; 32]);
+    assert_eq!(session.state.to_vec(), vec![3]);
+    assert_eq!(session.playerCount, 2);
+    assert_eq!(session.actionHashes, vec![[3u32; 8]; 2]);
+    assert_eq!(session.systemActionHash, [3u32; 8]);
+}
+
+#[test]
+fn broken_chains_are_rejected() {
+    assert!(matches!(
+        aggregate_segments(&[]),
+        Err(TurboError::Aggregation(_))
+    ));
+    assert!(matches!(
+        aggregate_segments(&[segment(0, 1, &[player(1)]), segment(2, 3, &[player(1)])]),
+        Err(TurboError::Aggregation(_))
+    ));
+    assert!(matches!(
+        aggregate_segments(&[segment(0, 1, &[player(1)]), segment(1, 2, &[player(2)])]),
+        Err(TurboError::Aggregation(_))
+    ));
+    assert!(matches!(
+        aggregate_segments(&[
+            segment(0, 1, &[player(1), player(2)]),
+            segment(1, 2, &[player(1)])
+        ]),
+        Err(TurboError::Aggregation(_))
+    ));
+    assert!(matches!(
+        aggregate_segments(&[
+            segment(0, 1, &[player(1)]),
+            segment(1, 2, &[player(1)]).with_config_hash(config_hash(&"hard"))
+        ]),
+        Err(TurboError::Aggregation(_))
+    ));
+}
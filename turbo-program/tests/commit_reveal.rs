@@ -0,0 +1,43 @@
+use turbo_program::commit_reveal::{move_commitment, CommitReveal};
+
+const SALT: [u8; 32] = [5; 32];
+
+#[test]
+fn reveals_are_checked_against_commitments() {
+    let mut round = CommitReveal::new(2);
+    round.commit(0, move_commitment(0, &SALT, b"rock")).unwrap();
+
+    // Nobody reveals before everyone committed
+    assert!(round.reveal(0, &SALT, b"rock", 0).is_err());
+
+    // Copying another player's commitment does not let a player reveal the same move
+    round.commit(1, move_commitment(0, &SALT, b"rock")).unwrap();
+    assert!(round.commit(1, [0; 32]).is_err());
+    assert!(round.reveal(1, &SALT, b"rock", 0).is_err());
+
+    assert!(round.reveal(0, &SALT, b"paper", 0).is_err());
+    assert!(round.reveal(0, &[6; 32], b"rock", 0).is_err());
+    round.reveal(0, &SALT, b"rock", 0).unwrap();
+    assert!(round.reveal(0, &SALT, b"rock", 0).is_err());
+    assert_eq!(round.revealed(0), Some(&b"rock"[..]));
+    assert_eq!(round.missing_reveals(), vec![1]);
+
+    // A player outside the round is refused instead of panicking
+    assert!(round.commit(2, [0; 32]).is_err());
+    assert!(round.reveal(2, &SALT, b"rock", 0).is_err());
+}
+
+#[test]
+fn reveals_close_at_the_deadline() {
+    let mut round = CommitReveal::new(1);
+    round.commit(0, move_commitment(0, &SALT, b"rock")).unwrap();
+    round.set_deadline(100);
+
+    assert!(round.reveal(0, &SALT, b"rock", 100).is_err());
+    round.reveal(0, &SALT, b"rock", 99).unwrap();
+    assert!(round.all_revealed());
+
+    round.reset();
+    assert_eq!(round.deadline(), None);
+    assert!(!round.is_committed(0));
+}
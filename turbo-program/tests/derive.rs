@@ -0,0 +1,145 @@
+use turbo_program::{
+    error::TurboError,
+    traits::{TurboAction, TurboActionSerialization},
+};
+
+#[derive(TurboAction, Debug, PartialEq)]
+enum TestAction {
+    Pass,
+    Move(u8),
+    Shoot {
+        x: u32,
+        y: u32,
+        power: i16,
+    },
+    #[turbo_action(tag = 10, rename = "place")]
+    Place([u8; 2], bool),
+    Wait(u64),
+}
+
+fn all_actions() -> Vec<TestAction> {
+    vec![
+        TestAction::Pass,
+        TestAction::Move(3),
+        TestAction::Shoot {
+            x: 0xdeadbeef,
+            y: 7,
+            power: -300,
+        },
+        TestAction::Place([1, 2], true),
+        TestAction::Wait(u64::MAX),
+    ]
+}
+
+#[test]
+fn binary_round_trip() {
+    for action in all_actions() {
+        let bytes = action.serialize();
+        let (decoded, rest) = TestAction::deserialize(&bytes).unwrap();
+        assert_eq!(decoded, action);
+        assert!(rest.is_empty());
+    }
+}
+
+#[test]
+fn binary_layout() {
+    assert_eq!(TestAction::Pass.serialize(), vec![0]);
+    assert_eq!(TestAction::Move(3).serialize(), vec![1, 3]);
+    assert_eq!(
+        TestAction::Shoot {
+            x: 1,
+            y: 2,
+            power: -1
+        }
+        .serialize(),
+        vec![2, 0, 0, 0, 1, 0, 0, 0, 2, 0xff, 0xff]
+    );
+    assert_eq!(
+        TestAction::Place([4, 5], true).serialize(),
+        vec![10, 4, 5, 1]
+    );
+    // Tags continue after an explicit tag
+    assert_eq!(TestAction::Wait(1).serialize()[0], 11);
+}
+
+#[test]
+fn stream_of_actions() {
+    let actions = all_actions();
+    let stream: Vec<u8> = actions.iter().flat_map(|a| a.serialize()).collect();
+
+    let mut remaining = &stream[..];
+    let mut decoded = Vec::new();
+    while !remaining.is_empty() {
+        let (action, next) = TestAction::deserialize(remaining).unwrap();
+        decoded.push(action);
+        remaining = next;
+    }
+    assert_eq!(decoded, actions);
+}
+
+#[test]
+fn json_round_trip() {
+    let cases = [
+        (r#"{"action": "Pass"}"#, TestAction::Pass),
+        (r#"{"action": "Move", "data": [3]}"#, TestAction::Move(3)),
+        (
+            r#"{"action": "Shoot", "data": [5, 6, -7]}"#,
+            TestAction::Shoot {
+                x: 5,
+                y: 6,
+                power: -7,
+            },
+        ),
+        (
+            r#"{"action": "place", "data": [[8, 9], false]}"#,
+            TestAction::Place([8, 9], false),
+        ),
+    ];
+
+    for (json, expected) in cases {
+        let bytes = TestAction::serialize_json(json).unwrap();
+        assert_eq!(bytes, expected.serialize());
+        assert_eq!(TestAction::deserialize(&bytes).unwrap().0, expected);
+    }
+}
+
+#[test]
+fn truncated_actions_are_errors() {
+    for action in all_actions() {
+        let bytes = action.serialize();
+        for len in 0..bytes.len() {
+            assert!(TestAction::deserialize(&bytes[..len]).is_err());
+        }
+    }
+}
+
+#[test]
+fn decode_errors_report_offset() {
+    assert_eq!(
+        TestAction::deserialize(&[2, 0, 0, 0, 1, 0]).unwrap_err(),
+        TurboError::Decode {
+            offset: 5,
+            message: "Unexpected end of action"
+        }
+    );
+    assert_eq!(
+        TestAction::deserialize(&[10, 1, 2, 3]).unwrap_err(),
+        TurboError::Decode {
+            offset: 3,
+            message: "Invalid bool field"
+        }
+    );
+}
+
+#[test]
+fn invalid_actions_are_errors() {
+    assert!(TestAction::deserialize(&[42]).is_err());
+    assert!(TestAction::deserialize(&[10, 1, 2, 3]).is_err());
+
+    assert!(TestAction::serialize_json("not json").is_err());
+    assert!(TestAction::serialize_json(r#"{"action": "Fly"}"#).is_err());
+    assert!(TestAction::serialize_json(r#"{"action": "Move"}"#).is_err());
+    assert!(TestAction::serialize_json(r#"{"action": "Move", "data": [256]}"#).is_err());
+    assert!(TestAction::serialize_json(r#"{"action": "Move", "data": [-1]}"#).is_err());
+    assert!(TestAction::serialize_json(r#"{"action": "place", "data": [[1], true]}"#).is_err());
+}
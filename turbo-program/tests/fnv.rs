@@ -0,0 +1,32 @@
+#[path = "vectors/fnv.rs"]
+mod vectors;
+
+use turbo_program::crypto::fnv::FnvHasher;
+use vectors::fnv_vectors;
+
+fn hash(data: &[u8]) -> ([u32; 8], usize) {
+    let mut hasher = FnvHasher::new();
+    hasher.next(data);
+    (hasher.get(), hasher.shift())
+}
+
+#[test]
+fn fixed_vectors() {
+    for (name, input, digest, shift) in fnv_vectors() {
+        assert_eq!(hash(&input), (digest, shift), "{}", name);
+    }
+}
+
+#[test]
+fn split_input_matches_single_call() {
+    let data: Vec<u8> = (0..23).collect();
+    let mut hasher = FnvHasher::new();
+    hasher.next(&data[..5]);
+    hasher.next(&data[5..]);
+    assert_eq!((hasher.get(), hasher.shift()), hash(&data));
+
+    let mut restored = FnvHasher::from_parts(hasher.get(), hasher.shift());
+    restored.next(&[1, 2, 3]);
+    hasher.next(&[1, 2, 3]);
+    assert_eq!(restored.get(), hasher.get());
+}
@@ -0,0 +1,134 @@
+use turbo_program::{
+    error::TurboError,
+    framing::{
+        decode_actions, decode_stamped, encode_actions, stamp_action, unstamp_action, ActionSender,
+        ACTION_FRAMING_VERSION,
+    },
+};
+
+#[test]
+fn small_player_indices_keep_one_byte() {
+    assert_eq!(ActionSender::Player(0).to_bytes().unwrap(), vec![0x00]);
+    assert_eq!(ActionSender::Player(0x6f).to_bytes().unwrap(), vec![0x6f]);
+    assert_eq!(ActionSender::System(0).to_bytes().unwrap(), vec![0x70]);
+    assert_eq!(ActionSender::System(0xf).to_bytes().unwrap(), vec![0x7f]);
+    // Players above the reserved range skip over it
+    assert_eq!(
+        ActionSender::Player(0x70).to_bytes().unwrap(),
+        vec![0x80, 0x01]
+    );
+}
+
+#[test]
+fn senders_round_trip() {
+    let senders = [
+        ActionSender::Player(0),
+        ActionSender::Player(0x6f),
+        ActionSender::Player(0x70),
+        ActionSender::Player(500),
+        ActionSender::Player(100_000),
+        ActionSender::System(0),
+        ActionSender::System(0xf),
+    ];
+
+    for sender in senders {
+        let mut bytes = sender.to_bytes().unwrap();
+        bytes.push(0xaa);
+        assert_eq!(ActionSender::decode(&bytes), Ok((sender, &[0xaa][..])));
+    }
+}
+
+#[test]
+fn invalid_senders_are_rejected() {
+    assert!(matches!(
+        ActionSender::decode(&[]),
+        Err(TurboError::Decode { offset: 0, .. })
+    ));
+    assert!(matches!(
+        ActionSender::decode(&[0x80]),
+        Err(TurboError::Decode { offset: 1, .. })
+    ));
+    assert!(matches!(
+        ActionSender::decode(&[0x81, 0x00]),
+        Err(TurboError::Decode { offset: 1, .. })
+    ));
+    assert!(matches!(
+        ActionSender::decode(&[0xff, 0xff, 0xff, 0xff, 0x7f]),
+        Err(TurboError::Decode { offset: 4, .. })
+    ));
+}
+
+#[test]
+fn overlong_and_non_minimal_senders_are_rejected() {
+    // The largest id still fits in 5 bytes
+    let max = ActionSender::Player(u32::MAX as usize - 0x10)
+        .to_bytes()
+        .unwrap();
+    assert_eq!(max, vec![0xff, 0xff, 0xff, 0xff, 0x0f]);
+    assert_eq!(
+        ActionSender::decode(&max),
+        Ok((ActionSender::Player(u32::MAX as usize - 0x10), &[][..]))
+    );
+    // Past it a player has no sender id at all
+    let mut out = Vec::new();
+    assert_eq!(
+        ActionSender::Player(u32::MAX as usize - 0xf).encode(&mut out),
+        Err(TurboError::UnknownPlayer(u32::MAX as usize - 0xf))
+    );
+    assert_eq!(
+        ActionSender::Player(usize::MAX).to_bytes(),
+        Err(TurboError::UnknownPlayer(usize::MAX))
+    );
+    assert!(out.is_empty());
+
+    // A run of zero continuation bytes would otherwise decode as a small id
+    let mut overlong = vec![0x80; 10];
+    overlong.push(0x01);
+    assert!(matches!(
+        ActionSender::decode(&overlong),
+        Err(TurboError::Decode { offset: 5, .. })
+    ));
+    assert!(matches!(
+        ActionSender::decode(&[0x80; 64]),
+        Err(TurboError::Decode { offset: 5, .. })
+    ));
+    assert!(matches!(
+        ActionSender::decode(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x00]),
+        Err(TurboError::Decode { offset: 5, .. })
+    ));
+
+    // Padding a small id with zero bytes is not the minimal encoding
+    assert!(matches!(
+        ActionSender::decode(&[0x80, 0x00]),
+        Err(TurboError::Decode { offset: 1, .. })
+    ));
+    assert!(matches!(
+        ActionSender::decode(&[0x85, 0x80, 0x00]),
+        Err(TurboError::Decode { offset: 2, .. })
+    ));
+}
+
+#[test]
+fn actions_are_versioned() {
+    let framed = encode_actions(&[0, 2]);
+    assert_eq!(framed, vec![ACTION_FRAMING_VERSION, 0, 2]);
+    assert_eq!(decode_actions(&framed), Ok(&[0, 2][..]));
+    assert!(decode_actions(&[]).is_err());
+    assert!(decode_actions(&[ACTION_FRAMING_VERSION + 1, 0, 2]).is_err());
+}
+
+#[test]
+fn timestamps_follow_the_sender() {
+    let action = [0x80, 0x01, 3];
+    let stamped = stamp_action(&action, 0x0102).unwrap();
+    assert_eq!(stamped, vec![0x80, 0x01, 0, 0, 0, 0, 0, 0, 0x01, 0x02, 3]);
+    assert_eq!(
+        decode_stamped(&stamped),
+        Ok((ActionSender::Player(0x70), 0x0102, &[3][..]))
+    );
+    assert_eq!(unstamp_action(&stamped), Ok((0x0102, action.to_vec())));
+    assert!(matches!(
+        decode_stamped(&stamped[..5]),
+        Err(TurboError::Decode { offset: 2, .. })
+    ));
+}
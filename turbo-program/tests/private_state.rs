@@ -0,0 +1,33 @@
+use turbo_program::{
+    metadata::ServerMetadata,
+    private_state::{commit_private_state, private_state_bytes, PrivateStateReveal},
+};
+
+#[test]
+fn reveal_matches_commitment() {
+    let server_metadata = ServerMetadata {
+        random_seed: [42; 16],
+    };
+    let hand = vec![3u8, 14, 27];
+
+    let reveal = PrivateStateReveal::new(&hand, &server_metadata);
+    assert!(reveal.verify());
+    assert_eq!(reveal.decode::<Vec<u8>>(), Some(hand.clone()));
+
+    // The salt depends on the server seed
+    let other = PrivateStateReveal::new(
+        &hand,
+        &ServerMetadata {
+            random_seed: [43; 16],
+        },
+    );
+    assert_ne!(other.commitment, reveal.commitment);
+
+    let mut tampered = reveal.clone();
+    tampered.private_state = private_state_bytes(&vec![3u8, 14, 28]);
+    assert!(!tampered.verify());
+    assert_ne!(
+        commit_private_state(&tampered.private_state, &reveal.salt),
+        reveal.commitment
+    );
+}
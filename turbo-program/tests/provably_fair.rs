@@ -0,0 +1,21 @@
+use turbo_program::{
+    metadata::ServerMetadata,
+    rand::provably_fair::{commit_server_seed, verify_server_seed, ServerSeedReveal},
+};
+
+#[test]
+fn reveal_matches_commitment() {
+    let server_metadata = ServerMetadata {
+        random_seed: [42; 16],
+    };
+    let commitment = server_metadata.seed_commitment();
+    assert_eq!(commitment, commit_server_seed(&[42; 16]));
+
+    let reveal = ServerSeedReveal::new(&server_metadata);
+    assert!(reveal.verify());
+    assert_eq!(reveal.commitment, commitment);
+
+    let mut other_seed = [42; 16];
+    other_seed[15] = 43;
+    assert!(!verify_server_seed(&other_seed, &commitment));
+}
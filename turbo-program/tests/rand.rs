@@ -0,0 +1,184 @@
+use turbo_program::rand::bn_randomizer::BnRandomizer;
+
+/// Pearson's chi-squared statistic of `counts` against a uniform distribution.
+fn chi_squared(counts: &[u64]) -> f64 {
+    let total: u64 = counts.iter().sum();
+    let expected = total as f64 / counts.len() as f64;
+    counts
+        .iter()
+        .map(|c| (*c as f64 - expected).powi(2) / expected)
+        .sum()
+}
+
+#[test]
+fn next_below_is_uniform() {
+    let mut rand = BnRandomizer::new();
+    let mut counts = [0u64; 6];
+    for _ in 0..12_000 {
+        counts[rand.next_below(6) as usize] += 1;
+    }
+    // 99.9th percentile of chi-squared with 5 degrees of freedom
+    assert!(chi_squared(&counts) < 20.52, "{:?}", counts);
+}
+
+#[test]
+fn next_range_stays_in_range() {
+    let mut rand = BnRandomizer::new();
+    for _ in 0..1_000 {
+        let value = rand.next_range(10..13);
+        assert!((10..13).contains(&value));
+    }
+    assert_eq!(rand.next_range(7..8), 7);
+}
+
+#[test]
+fn next_bool_follows_probability() {
+    let mut rand = BnRandomizer::new();
+    let hits = (0..10_000).filter(|_| rand.next_bool(0.25)).count();
+    assert!((2_300..2_700).contains(&hits), "{}", hits);
+
+    assert!((0..100).all(|_| rand.next_bool(1.0)));
+    assert!((0..100).all(|_| !rand.next_bool(0.0)));
+    assert!(!rand.next_bool(f64::NAN));
+}
+
+#[test]
+fn shuffle_is_a_uniform_permutation() {
+    let mut rand = BnRandomizer::new();
+    let permutations = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    let mut counts = [0u64; 6];
+    for _ in 0..6_000 {
+        let mut items = [0, 1, 2];
+        rand.shuffle(&mut items);
+        counts[permutations.iter().position(|p| *p == items).unwrap()] += 1;
+    }
+    assert!(chi_squared(&counts) < 20.52, "{:?}", counts);
+}
+
+#[test]
+fn weighted_choice_follows_weights() {
+    let mut rand = BnRandomizer::new();
+    let mut counts = [0u64; 3];
+    for _ in 0..8_000 {
+        counts[rand.weighted_index(&[1, 0, 3]).unwrap()] += 1;
+    }
+    assert_eq!(counts[1], 0);
+    assert!((1_800..2_200).contains(&counts[0]), "{:?}", counts);
+
+    assert_eq!(rand.weighted_index(&[0, 0]), None);
+    assert_eq!(rand.choose_weighted(&["a", "b"], &[0, 5]), Some(&"b"));
+    assert_eq!(rand.choose::<u8>(&[]), None);
+}
+
+#[test]
+fn fill_bytes_uses_little_endian_words() {
+    let mut rand = BnRandomizer::new();
+    let mut expected = rand.clone();
+
+    let mut bytes = [0u8; 6];
+    rand.fill_bytes(&mut bytes);
+    assert_eq!(bytes[..4], expected.next_u32().to_le_bytes());
+    assert_eq!(bytes[4..], expected.next_u32().to_le_bytes()[..2]);
+}
+
+#[test]
+fn same_seed_same_sequence() {
+    let mut a = BnRandomizer::new();
+    let mut b = BnRandomizer::new();
+    let mut items_a: Vec<u32> = (0..20).collect();
+    let mut items_b = items_a.clone();
+    a.shuffle(&mut items_a);
+    b.shuffle(&mut items_b);
+    assert_eq!(items_a, items_b);
+
+    let mut restored = BnRandomizer::from_parts(a.current_seed(), a.nonce());
+    assert_eq!(a.next_range(0..1_000), restored.next_range(0..1_000));
+    assert_eq!(a.next_u64(), restored.next_u64());
+}
+
+#[test]
+fn rng_core_matches_inherent_methods() {
+    use rand::{seq::SliceRandom, Rng, RngCore};
+
+    let mut rand = BnRandomizer::new();
+    let mut expected = rand.clone();
+    assert_eq!(RngCore::next_u32(&mut rand), expected.next_u32());
+    assert_eq!(RngCore::next_u64(&mut rand), expected.next_u64());
+
+    let mut bytes = [0u8; 7];
+    let mut expected_bytes = [0u8; 7];
+    RngCore::fill_bytes(&mut rand, &mut bytes);
+    expected.fill_bytes(&mut expected_bytes);
+    assert_eq!(bytes, expected_bytes);
+
+    // Distributions from `rand` run on top of it and stay deterministic
+    let mut a = BnRandomizer::new();
+    let mut b = BnRandomizer::new();
+    let mut items: Vec<u32> = (0..10).collect();
+    let mut other = items.clone();
+    items.shuffle(&mut a);
+    other.shuffle(&mut b);
+    assert_eq!(items, other);
+    assert_eq!(a.gen_range(0..100u32), b.gen_range(0..100u32));
+}
+
+fn context_inner() -> turbo_program::context::TurboActionContextInner {
+    use substrate_bn::{AffineG1, Fr, Group, G1};
+    use turbo_program::{
+        crypto::bn_serialize::bn254_export_affine_g1_memcpy,
+        metadata::{PlayerMetadata, ServerMetadata},
+    };
+
+    let player_point = AffineG1::from_jacobian(G1::one() * Fr::from_str("5").unwrap()).unwrap();
+    turbo_program::context::TurboActionContextInner::new(
+        &ServerMetadata {
+            random_seed: bn254_export_affine_g1_memcpy(&AffineG1::one()),
+        },
+        &PlayerMetadata::new(bn254_export_affine_g1_memcpy(&player_point)),
+        0,
+    )
+}
+
+#[test]
+fn named_streams_are_independent() {
+    let mut a = context_inner();
+    let mut b = context_inner();
+
+    // Extra draws on the main stream or another label do not shift the loot stream
+    a.rand_u32();
+    a.rng("cosmetic").next_u64();
+    assert_eq!(a.rng("loot").next_u32(), b.rng("loot").next_u32());
+    assert_eq!(a.rng("loot").next_u32(), b.rng("loot").next_u32());
+
+    assert_ne!(
+        a.rng("loot").current_seed(),
+        a.rng("cosmetic").current_seed()
+    );
+    assert_ne!(a.rng("loot").current_seed(), a.rand().current_seed());
+}
+
+#[test]
+fn named_streams_survive_checkpoints() {
+    use turbo_program::context::TurboActionContextInner;
+
+    let mut context = context_inner();
+    context.rng("loot").next_u32();
+    let mut restored: TurboActionContextInner =
+        TurboActionContextInner::from_checkpoint(&context.checkpoint(), 0);
+
+    assert_eq!(
+        context.rng("loot").next_u32(),
+        restored.rng("loot").next_u32()
+    );
+    assert_eq!(
+        context.rng("fresh").next_u32(),
+        restored.rng("fresh").next_u32()
+    );
+}
@@ -0,0 +1,65 @@
+//! Fixed vectors for `FnvHasher`, computed with the zkVM arithmetic: a 32-bit FNV step per byte
+//! into its own limb and a multiplication by the 256-bit prime modulo 2^256 every 8 bytes.
+//!
+//! Checked on the host by `turbo-program/tests/fnv.rs` and in the zkVM by
+//! `script/tests/fnv_conformance.rs`.
+
+/// Name, input, digest limbs and shift of every vector.
+pub fn fnv_vectors() -> Vec<(&'static str, Vec<u8>, [u32; 8], usize)> {
+    vec![
+        (
+            "empty input is the offset",
+            vec![],
+            [
+                0xcaee0535, 0x1023b4c8, 0x47b6bbb3, 0xc8b15368, 0xc4e576cc, 0x2d98c384, 0xaac55036,
+                0xdd268dbc,
+            ],
+            0,
+        ),
+        (
+            "partial word",
+            b"abc".to_vec(),
+            [
+                0xc8b2633c, 0x1235679e, 0xb4a9a870, 0xc8b15368, 0xc4e576cc, 0x2d98c384, 0xaac55036,
+                0xdd268dbc,
+            ],
+            3,
+        ),
+        (
+            "full word",
+            (0..8).collect(),
+            [
+                0xa9b2326f, 0x3135986b, 0x95a977a3, 0x5a265171, 0xbd39fcd8, 0x487bc413, 0x049d3b90,
+                0xdeb11d61,
+            ],
+            8,
+        ),
+        (
+            "full word is multiplied on the next byte",
+            (0..9).collect(),
+            [
+                0x26faa57f, 0x3d525d4c, 0x8a00e74d, 0x0322f082, 0x6769a005, 0x35d3565f, 0x9ba204be,
+                0x791360ba,
+            ],
+            1,
+        ),
+        (
+            "long input",
+            (0..64).collect(),
+            [
+                0xdac408c7, 0x909e902c, 0x1cd7cd35, 0x66c3204e, 0x9d9bf5bc, 0x737adee6, 0xfc38dba5,
+                0x72ccfa09,
+            ],
+            8,
+        ),
+        (
+            "long input of high bytes",
+            vec![0xff; 100],
+            [
+                0xe694346e, 0x90ac381c, 0xdc99fad6, 0x460501da, 0x8c749a8c, 0x600a60cf, 0x07fb92dc,
+                0x94fb73a3,
+            ],
+            4,
+        ),
+    ]
+}
@@ -0,0 +1,208 @@
+use alloy_sol_types::SolValue;
+use sp1_sdk::{EnvProver, SP1Stdin};
+use turbo_program::{
+    error::TurboError,
+    framing::{encode_actions, stamp_action},
+    game::TurboGame,
+    metadata::{PlayerMetadata, ServerMetadata},
+    transcript::TurboPublicValues,
+};
+
+use crate::session::TurboSession;
+
+/// Seeds and actions of a recorded session. `player_metadata` are the players present before the
+/// first action, later players join with system actions in `actions`.
+pub struct TurboTranscript<G: TurboGame> {
+    pub config: G::Config,
+    pub server_metadata: ServerMetadata,
+    pub player_metadata: Vec<PlayerMetadata>,
+    /// Server timestamp and raw action (the encoded `ActionSender` followed by the action bytes)
+    /// of every action, system actions included.
+    pub actions: Vec<(u64, Vec<u8>)>,
+}
+
+/// What a run of the game commits: the ABI-encoded `TurboGame::Output`, the state hash, every
+/// player's action hash and the system action hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurboSnapshot {
+    pub state: Vec<u8>,
+    pub state_hash: [u8; 32],
+    pub action_hashes: Vec<[u32; 8]>,
+    pub system_action_hash: [u32; 8],
+}
+
+/// First point where the native session and the zkVM program disagree.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    /// Index of the action after which the results differ, `None` if they differ before any action.
+    pub action_index: Option<usize>,
+    pub native: TurboSnapshot,
+    /// Committed values, or the execution error if the program failed.
+    pub zkvm: Result<TurboSnapshot, String>,
+}
+
+/// Run `transcript` through `TurboSession` and through `elf` with `client.execute`, and find the
+/// first action where the results diverge.
+///
+/// The program is executed once with a trace of the state hash after every action (see
+/// `trace_turbo_program`), the first hash that differs from the session's is the divergence, even
+/// if the results agree again later. Only that prefix is executed again for the report. If the
+/// program fails, the shortest failing prefix is bisected, a failing run keeps failing with more
+/// actions, and the run just before it is traced for an earlier divergence.
+/// Rejected actions are kept in the transcript, both paths must skip them the same way.
+pub fn find_divergence<G: TurboGame>(
+    client: &EnvProver,
+    elf: &[u8],
+    transcript: &TurboTranscript<G>,
+) -> Result<Option<Divergence>, TurboError> {
+    let native = replay_native(transcript)?;
+    let first_mismatch = |trace: Vec<[u8; 32]>| {
+        native
+            .iter()
+            .zip(trace)
+            .position(|(native, state_hash)| native.state_hash != state_hash)
+    };
+
+    let total = transcript.actions.len();
+    let prefix = match execute_trace(client, elf, transcript, total) {
+        Ok(trace) => first_mismatch(trace),
+        Err(_) => {
+            let (mut passing, mut failing) = (0, total);
+            while passing < failing {
+                let prefix = (passing + failing) / 2;
+                if execute_prefix(client, elf, transcript, prefix).is_err() {
+                    failing = prefix;
+                } else {
+                    passing = prefix + 1;
+                }
+            }
+            let earlier = failing
+                .checked_sub(1)
+                .and_then(|passing| execute_trace(client, elf, transcript, passing).ok())
+                .and_then(first_mismatch);
+            Some(earlier.unwrap_or(failing))
+        }
+    };
+    let Some(prefix) = prefix else {
+        return Ok(None);
+    };
+
+    Ok(Some(Divergence {
+        action_index: prefix.checked_sub(1),
+        native: native[prefix].clone(),
+        zkvm: execute_prefix(client, elf, transcript, prefix),
+    }))
+}
+
+/// Panic with a readable report if the native session and `elf` diverge on `transcript`.
+pub fn assert_deterministic<G: TurboGame>(
+    client: &EnvProver,
+    elf: &[u8],
+    transcript: &TurboTranscript<G>,
+) {
+    match find_divergence(client, elf, transcript) {
+        Ok(None) => {}
+        Ok(Some(divergence)) => panic!(
+            "Native and zkVM results diverge after action {:?} ({:?}):\nnative: {:?}\nzkvm: {:?}",
+            divergence.action_index,
+            divergence.action_index.map(|i| &transcript.actions[i]),
+            divergence.native,
+            divergence.zkvm
+        ),
+        Err(e) => panic!("Failed to check determinism: {}", e),
+    }
+}
+
+/// Snapshot of the native session before any action and after every action.
+fn replay_native<G: TurboGame>(
+    transcript: &TurboTranscript<G>,
+) -> Result<Vec<TurboSnapshot>, TurboError> {
+    let mut session = TurboSession::<G>::with_server_metadata(
+        transcript.config.clone(),
+        transcript.server_metadata.clone(),
+    );
+    for player_metadata in &transcript.player_metadata {
+        session.join(player_metadata.clone())?;
+    }
+
+    let snapshot = |session: &TurboSession<G>| TurboSnapshot {
+        state: session.output().abi_encode(),
+        state_hash: session.state_hash(),
+        action_hashes: session.action_hashes(),
+        system_action_hash: session.system_action_hash(),
+    };
+
+    let mut snapshots = vec![snapshot(&session)];
+    for (timestamp, action) in &transcript.actions {
+        match session.dispatch_at(action, *timestamp) {
+            Ok(()) | Err(TurboError::ActionRejected(_)) => {}
+            Err(e) => return Err(e),
+        }
+        snapshots.push(snapshot(&session));
+    }
+    Ok(snapshots)
+}
+
+fn execute_prefix<G: TurboGame>(
+    client: &EnvProver,
+    elf: &[u8],
+    transcript: &TurboTranscript<G>,
+    actions: usize,
+) -> Result<TurboSnapshot, String> {
+    let stdin = prefix_stdin(transcript, actions, false)?;
+    let (output, _) = client
+        .execute(elf, &stdin)
+        .run()
+        .map_err(|e| e.to_string())?;
+    let public_values = TurboPublicValues::abi_decode(output.as_slice())
+        .map_err(|e| format!("Failed to decode public values: {}", e))?;
+
+    Ok(TurboSnapshot {
+        state: public_values.state.to_vec(),
+        state_hash: public_values.stateHash.0,
+        action_hashes: public_values.actionHashes,
+        system_action_hash: public_values.systemActionHash,
+    })
+}
+
+// State hash before the first action and after every action of the prefix
+fn execute_trace<G: TurboGame>(
+    client: &EnvProver,
+    elf: &[u8],
+    transcript: &TurboTranscript<G>,
+    actions: usize,
+) -> Result<Vec<[u8; 32]>, String> {
+    let stdin = prefix_stdin(transcript, actions, true)?;
+    let (output, _) = client
+        .execute(elf, &stdin)
+        .run()
+        .map_err(|e| e.to_string())?;
+
+    Ok(output
+        .as_slice()
+        .chunks_exact(32)
+        .map(|state_hash| state_hash.try_into().unwrap())
+        .collect())
+}
+
+fn prefix_stdin<G: TurboGame>(
+    transcript: &TurboTranscript<G>,
+    actions: usize,
+    trace: bool,
+) -> Result<SP1Stdin, String> {
+    // Same layout as `TurboSession::sp1_stdin`, rejected actions included
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&transcript.server_metadata);
+    stdin.write(&transcript.config);
+    stdin.write(&transcript.player_metadata);
+    stdin.write(&None::<Vec<u8>>);
+    let mut stamped = Vec::new();
+    for (timestamp, action) in &transcript.actions[..actions] {
+        stamped.extend(stamp_action(action, *timestamp).map_err(|e| e.to_string())?);
+    }
+    stdin.write(&encode_actions(&stamped));
+    // Views are not compared, any view secret will do
+    stdin.write(&rand::random::<[u8; 32]>());
+    stdin.write(&trace);
+    Ok(stdin)
+}
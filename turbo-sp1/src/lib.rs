@@ -1,4 +1,5 @@
 pub use turbo_program::*;
+pub mod determinism;
 pub mod proof;
 pub mod proof_worker;
 pub mod prove_queue;
@@ -1,5 +1,4 @@
 use lazy_static::lazy_static;
-use sp1_sdk::SP1ProofWithPublicValues;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex as StdMutex;
@@ -8,16 +7,24 @@ use alloy_sol_types::SolValue;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sp1_sdk::{
-    EnvProver, ExecutionReport, HashableKey, SP1ProvingKey, SP1PublicValues, SP1VerifyingKey,
+    EnvProver, ExecutionReport, HashableKey, SP1Proof, SP1ProvingKey, SP1PublicValues, SP1Stdin,
+    SP1VerifyingKey,
 };
 use tokio::sync::Mutex;
-use turbo_program::traits::TurboActionSerialization;
+use turbo_program::{
+    aggregation::aggregate_segments,
+    error::TurboError,
+    game::TurboGame,
+    transcript::{TurboAggregatedPublicValues, TurboPublicValues},
+};
 
 use crate::session::TurboSession;
 
+// Proving and verifying key of a program, set up once per ELF
+type SetupKeys = Arc<(SP1ProvingKey, SP1VerifyingKey)>;
+
 lazy_static! {
-    static ref SETUP_CACHE: StdMutex<HashMap<Vec<u8>, Arc<(SP1ProvingKey, SP1VerifyingKey)>>> =
-        StdMutex::new(HashMap::new());
+    static ref SETUP_CACHE: StdMutex<HashMap<Vec<u8>, SetupKeys>> = StdMutex::new(HashMap::new());
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,13 +35,25 @@ pub enum ProofType {
     Plonk,
 }
 
-async fn setup_circuit(
-    client: Arc<EnvProver>,
-    elf: Arc<Vec<u8>>,
-) -> Result<Arc<(SP1ProvingKey, SP1VerifyingKey)>, &'static str> {
-    let mut cache = SETUP_CACHE.lock().map_err(|_| "Failed to lock cache")?;
+impl ProofType {
+    /// Parse the proof type names used by the routes: core, compressed, groth16 or plonk.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "core" => Some(Self::Core),
+            "compressed" => Some(Self::Compressed),
+            "groth16" => Some(Self::Groth16),
+            "plonk" => Some(Self::Plonk),
+            _ => None,
+        }
+    }
+}
+
+async fn setup_circuit(client: Arc<EnvProver>, elf: Arc<Vec<u8>>) -> Result<SetupKeys, TurboError> {
+    let mut cache = SETUP_CACHE
+        .lock()
+        .map_err(|_| TurboError::Setup("Failed to lock cache".into()))?;
     if let Some(arc) = cache.get(&elf[..]) {
-        return Ok(arc.clone());
+        Ok(arc.clone())
     } else {
         let (pk, vk) = client.setup(&elf);
         let arc = Arc::new((pk.clone(), vk.clone()));
@@ -43,20 +62,65 @@ async fn setup_circuit(
     }
 }
 
-async fn execute_circuit<
-    PublicState: Default
-        + SolValue
-        + Serialize
-        + From<<<PublicState as SolValue>::SolType as alloy_sol_types::SolType>::RustType>
-        + Send
-        + Sync,
-    PrivateState: Default + Send + Sync,
-    GameAction: TurboActionSerialization + Send + Sync,
->(
-    session: Arc<Mutex<TurboSession<PublicState, PrivateState, GameAction>>>,
+fn decode_public_values<G: TurboGame>(
+    public_values: &[u8],
+) -> Result<(G::Output, serde_json::Value), TurboError> {
+    let public_values = TurboPublicValues::abi_decode(public_values)
+        .map_err(|e| TurboError::Execution(format!("Failed to decode public values: {}", e)))?;
+    decode_session::<G>(&public_values)
+}
+
+fn decode_aggregated_public_values<G: TurboGame>(
+    public_values: &[u8],
+) -> Result<(G::Output, serde_json::Value), TurboError> {
+    let public_values = TurboAggregatedPublicValues::abi_decode(public_values)
+        .map_err(|e| TurboError::Execution(format!("Failed to decode public values: {}", e)))?;
+    let (state, mut transcript) = decode_session::<G>(&public_values.session)?;
+    transcript["segment_vkey"] = json!(public_values.segmentVkey);
+    transcript["segment_count"] = json!(public_values.segmentCount);
+    Ok((state, transcript))
+}
+
+fn decode_session<G: TurboGame>(
+    public_values: &TurboPublicValues,
+) -> Result<(G::Output, serde_json::Value), TurboError> {
+    let state = G::Output::abi_decode(&public_values.state)
+        .map_err(|e| TurboError::Execution(format!("Failed to decode output state: {}", e)))?;
+
+    Ok((
+        state,
+        json!({
+            "prior_state_hash": format!("0x{}", hex::encode(public_values.priorStateHash)),
+            "state_hash": format!("0x{}", hex::encode(public_values.stateHash)),
+            "server_seed_commitment": format!("0x{}", hex::encode(public_values.serverSeedCommitment)),
+            "config_hash": format!("0x{}", hex::encode(public_values.configHash)),
+            "finished": public_values.finished,
+            "private_state_commitment": format!("0x{}", hex::encode(public_values.privateStateCommitment)),
+            "player_count": public_values.playerCount,
+            "player_seeds": public_values.playerSeeds,
+            "action_hashes": public_values.actionHashes,
+            "system_action_hash": public_values.systemActionHash,
+            "player_joined_at": public_values.playerJoinedAt,
+            "player_left_at": public_values.playerLeftAt,
+            "player_public_keys": public_values
+                .playerPublicKeys
+                .iter()
+                .map(|key| format!("0x{}", hex::encode(key)))
+                .collect::<Vec<_>>(),
+            "player_views": public_values
+                .playerViews
+                .iter()
+                .map(|view| format!("0x{}", hex::encode(view)))
+                .collect::<Vec<_>>(),
+        }),
+    ))
+}
+
+async fn execute_circuit<G: TurboGame>(
+    session: Arc<Mutex<TurboSession<G>>>,
     client: Arc<EnvProver>,
     elf: Arc<Vec<u8>>,
-) -> Result<(SP1PublicValues, ExecutionReport), &'static str> {
+) -> Result<(SP1PublicValues, ExecutionReport), TurboError> {
     // Setup the inputs
     let stdin = session.lock().await.sp1_stdin();
 
@@ -64,49 +128,31 @@ async fn execute_circuit<
     client
         .execute(&elf, &stdin)
         .run()
-        .map_err(|_| "Failed to execute circuit")
+        .map_err(|e| TurboError::Execution(e.to_string()))
 }
 
-pub async fn handle_proof_execute<
-    PublicState: Default
-        + SolValue
-        + Serialize
-        + From<<<PublicState as SolValue>::SolType as alloy_sol_types::SolType>::RustType>
-        + Send
-        + Sync,
-    PrivateState: Default + Send + Sync,
-    GameAction: TurboActionSerialization + Send + Sync,
->(
-    session: Arc<Mutex<TurboSession<PublicState, PrivateState, GameAction>>>,
+pub async fn handle_proof_execute<G: TurboGame>(
+    session: Arc<Mutex<TurboSession<G>>>,
     client: Arc<EnvProver>,
     elf: Arc<Vec<u8>>,
-) -> Result<serde_json::Value, &'static str> {
-    let (output, report) = execute_circuit(session, client, elf).await?;
+) -> Result<serde_json::Value, TurboError> {
+    let (output, report) = execute_circuit::<G>(session, client, elf).await?;
 
-    let state: PublicState =
-        PublicState::abi_decode(output.as_slice()).map_err(|_| "Failed to decode output state")?;
+    let (state, transcript) = decode_public_values::<G>(output.as_slice())?;
     Ok(json!({
         "cycle_count": report.total_instruction_count(),
-        "state": state
+        "state": state,
+        "transcript": transcript
     }))
 }
 
-pub async fn handle_proof_request<
-    PublicState: Default
-        + SolValue
-        + Serialize
-        + From<<<PublicState as SolValue>::SolType as alloy_sol_types::SolType>::RustType>
-        + Send
-        + Sync,
-    PrivateState: Default + Send + Sync,
-    GameAction: TurboActionSerialization + Send + Sync,
->(
-    session: Arc<Mutex<TurboSession<PublicState, PrivateState, GameAction>>>,
+pub async fn handle_proof_request<G: TurboGame>(
+    session: Arc<Mutex<TurboSession<G>>>,
     client: Arc<EnvProver>,
     elf: Arc<Vec<u8>>,
     proof_type: ProofType,
     proof_id: String,
-) -> Result<serde_json::Value, &'static str> {
+) -> Result<serde_json::Value, TurboError> {
     // Setup the inputs
     let stdin = session.lock().await.sp1_stdin();
 
@@ -114,46 +160,136 @@ pub async fn handle_proof_request<
     let (_, report) = client
         .execute(&elf, &stdin)
         .run()
-        .map_err(|_| "Failed to execute circuit")?;
+        .map_err(|e| TurboError::Execution(e.to_string()))?;
 
     let setup_arc = setup_circuit(client.clone(), elf).await?;
     let pk = &setup_arc.0;
     let vk = &setup_arc.1;
 
     let proof = match proof_type {
-        ProofType::Core => client
-            .prove(pk, &stdin)
-            .run()
-            .expect("failed to generate proof"),
-        ProofType::Compressed => client
-            .prove(pk, &stdin)
+        ProofType::Core => client.prove(pk, &stdin).run(),
+        ProofType::Compressed => client.prove(pk, &stdin).compressed().run(),
+        ProofType::Groth16 => client.prove(pk, &stdin).groth16().run(),
+        ProofType::Plonk => client.prove(pk, &stdin).plonk().run(),
+    }
+    .map_err(|e| TurboError::Prover(e.to_string()))?;
+
+    let (state, transcript) = decode_public_values::<G>(proof.public_values.as_slice())?;
+
+    std::fs::create_dir_all("proofs")
+        .map_err(|e| TurboError::Prover(format!("Failed to create proofs directory: {}", e)))?;
+    proof
+        .save(format!("proofs/{}.bin", proof_id))
+        .map_err(|e| TurboError::Prover(format!("Failed to save proof: {}", e)))?;
+
+    Ok(match proof_type {
+        ProofType::Core | ProofType::Compressed => json!({
+            "vkey": vk.bytes32().to_string(),
+            "public_values": format!("0x{}", hex::encode(proof.public_values.as_slice())),
+            "state": state,
+            "transcript": transcript,
+            "cycle_count": report.total_instruction_count()
+        }),
+        ProofType::Groth16 | ProofType::Plonk => json!({
+            "vkey": vk.bytes32().to_string(),
+            "public_values": format!("0x{}", hex::encode(proof.public_values.as_slice())),
+            "proof": format!("0x{}", hex::encode(proof.bytes())),
+            "state": state,
+            "transcript": transcript,
+            "cycle_count": report.total_instruction_count()
+        }),
+    })
+}
+
+/// Prove every segment of the session and aggregate them into a single proof.
+///
+/// Segments are proven as compressed proofs and verified by the aggregation program, which
+/// commits `TurboAggregatedPublicValues` for the whole session.
+pub async fn handle_aggregated_proof_request<G: TurboGame>(
+    session: Arc<Mutex<TurboSession<G>>>,
+    client: Arc<EnvProver>,
+    elf: Arc<Vec<u8>>,
+    aggregation_elf: Arc<Vec<u8>>,
+    proof_type: ProofType,
+    proof_id: String,
+) -> Result<serde_json::Value, TurboError> {
+    if let ProofType::Core = proof_type {
+        return Err(TurboError::Prover(
+            "Aggregated proofs must be compressed, groth16 or plonk".into(),
+        ));
+    }
+
+    let segment_stdins = session.lock().await.segment_stdins();
+
+    let segment_setup_arc = setup_circuit(client.clone(), elf).await?;
+    let segment_pk = &segment_setup_arc.0;
+    let segment_vk = &segment_setup_arc.1;
+
+    // Segment proofs must be compressed to be verified inside the aggregation program
+    let mut segment_public_values = Vec::new();
+    let mut segment_proofs = Vec::new();
+    for stdin in &segment_stdins {
+        let proof = client
+            .prove(segment_pk, stdin)
             .compressed()
             .run()
-            .expect("failed to generate proof"),
-        ProofType::Groth16 => client
-            .prove(pk, &stdin)
-            .groth16()
-            .run()
-            .expect("failed to generate proof"),
-        ProofType::Plonk => client
-            .prove(pk, &stdin)
-            .plonk()
-            .run()
-            .expect("failed to generate proof"),
-    };
+            .map_err(|e| TurboError::Prover(e.to_string()))?;
+        let SP1Proof::Compressed(reduce_proof) = proof.proof else {
+            return Err(TurboError::Prover("Segment proof is not compressed".into()));
+        };
+        segment_public_values.push(proof.public_values.to_vec());
+        segment_proofs.push(*reduce_proof);
+    }
+
+    // Check the chain before paying for the aggregation proof
+    let segments = segment_public_values
+        .iter()
+        .map(|public_values| {
+            TurboPublicValues::abi_decode(public_values).map_err(|e| {
+                TurboError::Execution(format!("Failed to decode public values: {}", e))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    aggregate_segments(&segments)?;
+
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&segment_vk.hash_u32());
+    stdin.write(&segment_public_values);
+    for proof in segment_proofs {
+        stdin.write_proof(proof, segment_vk.vk.clone());
+    }
+
+    let (_, report) = client
+        .execute(&aggregation_elf, &stdin)
+        .run()
+        .map_err(|e| TurboError::Execution(e.to_string()))?;
+
+    let setup_arc = setup_circuit(client.clone(), aggregation_elf).await?;
+    let pk = &setup_arc.0;
+    let vk = &setup_arc.1;
+
+    let proof = match proof_type {
+        ProofType::Core => unreachable!("rejected above"),
+        ProofType::Compressed => client.prove(pk, &stdin).compressed().run(),
+        ProofType::Groth16 => client.prove(pk, &stdin).groth16().run(),
+        ProofType::Plonk => client.prove(pk, &stdin).plonk().run(),
+    }
+    .map_err(|e| TurboError::Prover(e.to_string()))?;
 
-    let state: PublicState = PublicState::abi_decode(proof.public_values.as_slice()).unwrap();
+    let (state, transcript) = decode_aggregated_public_values::<G>(proof.public_values.as_slice())?;
 
-    std::fs::create_dir_all("proofs").map_err(|_| "Failed to create proofs directory")?;
+    std::fs::create_dir_all("proofs")
+        .map_err(|e| TurboError::Prover(format!("Failed to create proofs directory: {}", e)))?;
     proof
         .save(format!("proofs/{}.bin", proof_id))
-        .map_err(|_| "Failed to save proof")?;
+        .map_err(|e| TurboError::Prover(format!("Failed to save proof: {}", e)))?;
 
     Ok(match proof_type {
         ProofType::Core | ProofType::Compressed => json!({
             "vkey": vk.bytes32().to_string(),
             "public_values": format!("0x{}", hex::encode(proof.public_values.as_slice())),
             "state": state,
+            "transcript": transcript,
             "cycle_count": report.total_instruction_count()
         }),
         ProofType::Groth16 | ProofType::Plonk => json!({
@@ -161,6 +297,7 @@ pub async fn handle_proof_request<
             "public_values": format!("0x{}", hex::encode(proof.public_values.as_slice())),
             "proof": format!("0x{}", hex::encode(proof.bytes())),
             "state": state,
+            "transcript": transcript,
             "cycle_count": report.total_instruction_count()
         }),
     })
@@ -1,41 +1,30 @@
 use std::sync::Arc;
 
-use alloy_sol_types::SolValue;
-use serde::Serialize;
 use sp1_sdk::EnvProver;
 use tokio::sync::{mpsc, Mutex};
-use turbo_program::traits::TurboActionSerialization;
+use turbo_program::game::TurboGame;
 
 use crate::{
-    proof::{handle_proof_request, ProofType},
+    proof::{handle_aggregated_proof_request, handle_proof_request, ProofType},
     prove_queue::{ProveQueue, ProveStatus},
     session::TurboSession,
 };
 
 type TaskId = String;
-pub type ProofJob<PublicState, PrivateState, GameAction> =
-    (TaskId, ProofRequest<PublicState, PrivateState, GameAction>);
+pub type ProofJob<G> = (TaskId, ProofRequest<G>);
 
 #[derive(Clone)]
-pub struct ProofRequest<
-    PublicState: Serialize + Default + Send + Sync,
-    PrivateState: Default + Send + Sync,
-    GameAction: TurboActionSerialization + Send + Sync,
-> {
-    session: Arc<Mutex<TurboSession<PublicState, PrivateState, GameAction>>>,
+pub struct ProofRequest<G: TurboGame> {
+    session: Arc<Mutex<TurboSession<G>>>,
     proof_type: ProofType,
     client: Arc<EnvProver>,
     elf: Arc<Vec<u8>>,
+    aggregation_elf: Option<Arc<Vec<u8>>>,
 }
 
-impl<PublicState, PrivateState, GameAction> ProofRequest<PublicState, PrivateState, GameAction>
-where
-    PublicState: Serialize + Default + Send + Sync,
-    PrivateState: Default + Send + Sync,
-    GameAction: TurboActionSerialization + Send + Sync,
-{
+impl<G: TurboGame> ProofRequest<G> {
     pub fn new(
-        session: Arc<Mutex<TurboSession<PublicState, PrivateState, GameAction>>>,
+        session: Arc<Mutex<TurboSession<G>>>,
         proof_type: ProofType,
         client: Arc<EnvProver>,
         elf: Arc<Vec<u8>>,
@@ -45,25 +34,22 @@ where
             proof_type,
             client,
             elf,
+            aggregation_elf: None,
         }
     }
+
+    /// Prove every session segment and aggregate them with `aggregation_elf` into one proof.
+    pub fn aggregated(mut self, aggregation_elf: Arc<Vec<u8>>) -> Self {
+        self.aggregation_elf = Some(aggregation_elf);
+        self
+    }
 }
 /// Spawn `num_workers` background tasks that consume `rx_jobs`.
-pub fn spawn_proof_workers<PublicState, PrivateState, GameAction>(
+pub fn spawn_proof_workers<G: TurboGame>(
     num_workers: usize,
-    rx_jobs: mpsc::UnboundedReceiver<ProofJob<PublicState, PrivateState, GameAction>>,
+    rx_jobs: mpsc::UnboundedReceiver<ProofJob<G>>,
     queue: Arc<ProveQueue>,
-) where
-    PublicState: Default
-        + SolValue
-        + Serialize
-        + From<<<PublicState as SolValue>::SolType as alloy_sol_types::SolType>::RustType>
-        + Send
-        + Sync
-        + 'static,
-    PrivateState: Default + Send + Sync + 'static,
-    GameAction: TurboActionSerialization + Send + Sync + 'static,
-{
+) {
     let rx = Arc::new(Mutex::new(rx_jobs));
 
     for _ in 0..num_workers {
@@ -85,14 +71,29 @@ pub fn spawn_proof_workers<PublicState, PrivateState, GameAction>(
 
                 queue.set_status(&task_id, ProveStatus::InProgress);
 
-                let result = handle_proof_request::<PublicState, PrivateState, GameAction>(
-                    job.session,
-                    job.client,
-                    job.elf,
-                    job.proof_type,
-                    task_id.clone(),
-                )
-                .await
+                let result = match job.aggregation_elf {
+                    Some(aggregation_elf) => {
+                        handle_aggregated_proof_request::<G>(
+                            job.session,
+                            job.client,
+                            job.elf,
+                            aggregation_elf,
+                            job.proof_type,
+                            task_id.clone(),
+                        )
+                        .await
+                    }
+                    None => {
+                        handle_proof_request::<G>(
+                            job.session,
+                            job.client,
+                            job.elf,
+                            job.proof_type,
+                            task_id.clone(),
+                        )
+                        .await
+                    }
+                }
                 .map(ProveStatus::Done)
                 .unwrap_or_else(|e| ProveStatus::Error(e.to_string()));
 
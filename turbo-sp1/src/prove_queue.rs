@@ -5,6 +5,8 @@ use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProveStatus {
+    // An auto-proof registered before the game ended, queued once it does
+    WaitingForGameEnd,
     Queued,
     InProgress,
     Done(serde_json::Value),
@@ -29,11 +31,17 @@ impl ProveQueue {
     }
 
     pub fn enqueue_task(&self) -> String {
+        self.insert_task(ProveStatus::Queued)
+    }
+
+    /// Reserve a task for a proof that can only start once the game ends.
+    pub fn enqueue_waiting_task(&self) -> String {
+        self.insert_task(ProveStatus::WaitingForGameEnd)
+    }
+
+    fn insert_task(&self, status: ProveStatus) -> String {
         let id = Uuid::new_v4().to_string();
-        self.tasks
-            .lock()
-            .unwrap()
-            .insert(id.clone(), ProveStatus::Queued);
+        self.tasks.lock().unwrap().insert(id.clone(), status);
         id
     }
 
@@ -1,51 +1,45 @@
-use alloy_sol_types::SolValue;
 use futures_util::{SinkExt, StreamExt};
-use serde::Serialize;
 use serde_json::{json, Value};
 use std::{convert::Infallible, sync::Arc};
 use tokio::sync::{mpsc, Mutex};
 use warp::Filter;
 
 use sp1_sdk::ProverClient;
-use turbo_program::{program::TurboReducer, traits::TurboActionSerialization};
+use turbo_program::{error::TurboError, game::TurboGame};
 
 use crate::proof::{handle_proof_execute, ProofType};
 use crate::proof_worker::{spawn_proof_workers, ProofJob, ProofRequest};
 use crate::prove_queue::{ProveQueue, ProveStatus};
 use crate::session::TurboSession;
 use crate::session_manager::SessionManager;
-use crate::session_simple::{create_session_json, dispatch_actions};
+use crate::session_simple::{
+    create_session_json, dispatch_actions, parse_config, parse_player_metadata,
+};
 use crate::warp::rejection::{handle_rejection, ServerError};
 
-pub fn turbo_sp1_routes<PublicState, PrivateState, GameAction>(
+pub fn turbo_sp1_routes<G: TurboGame>(
     elf: &[u8],
-    reducer: TurboReducer<PublicState, PrivateState, GameAction>,
     num_workers: usize,
-) -> impl Filter<Extract = (impl warp::Reply,), Error = Infallible> + Clone
-where
-    PublicState: Default
-        + SolValue
-        + Serialize
-        + From<<<PublicState as SolValue>::SolType as alloy_sol_types::SolType>::RustType>
-        + Send
-        + Sync
-        + 'static,
-    PrivateState: Default + Serialize + Send + Sync + 'static,
-    GameAction: TurboActionSerialization + Send + Sync + 'static,
-{
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Infallible> + Clone {
+    turbo_sp1_routes_with_aggregation::<G>(elf, None, num_workers)
+}
+
+/// Same as `turbo_sp1_routes`, with `aggregation_elf` WebSocket proofs can set `"aggregate": true`
+/// to prove every session segment and aggregate them into a single proof.
+pub fn turbo_sp1_routes_with_aggregation<G: TurboGame>(
+    elf: &[u8],
+    aggregation_elf: Option<&[u8]>,
+    num_workers: usize,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Infallible> + Clone {
     let client_arc = Arc::new(ProverClient::from_env());
     let elf_arc = Arc::new(elf.to_vec());
+    let aggregation_elf_arc = aggregation_elf.map(|elf| Arc::new(elf.to_vec()));
     let prove_queue_arc = Arc::new(ProveQueue::new());
     let session_manager_arc = Arc::new(Mutex::new(SessionManager::new()));
-    let (tx_jobs, rx_jobs) =
-        mpsc::unbounded_channel::<ProofJob<PublicState, PrivateState, GameAction>>();
+    let (tx_jobs, rx_jobs) = mpsc::unbounded_channel::<ProofJob<G>>();
     let tx_jobs_arc = Arc::new(tx_jobs);
 
-    spawn_proof_workers::<PublicState, PrivateState, GameAction>(
-        num_workers,
-        rx_jobs,
-        prove_queue_arc.clone(),
-    );
+    spawn_proof_workers::<G>(num_workers, rx_jobs, prove_queue_arc.clone());
 
     let execute_client = client_arc.clone();
     let execute_elf = elf_arc.clone();
@@ -53,7 +47,7 @@ where
     let execute_route = warp::path!("execute")
         .and(warp::post())
         .and(warp::body::json())
-        .and_then(move |actions: serde_json::Value| {
+        .and_then(move |request: serde_json::Value| {
             let client = execute_client.clone();
             let elf = execute_elf.clone();
             let session_manager = execute_session_manager.clone();
@@ -61,13 +55,12 @@ where
             async move {
                 let session = {
                     let mut session_manager_guard = session_manager.lock().await;
-                    let session_id =
-                        match create_session_json(&mut session_manager_guard, reducer, actions)
-                            .await
-                        {
-                            Ok(id) => id,
-                            Err(err) => return Err(ServerError::bad_request(err.to_string())),
-                        };
+                    let session_id = match create_session_json(&mut session_manager_guard, request)
+                        .await
+                    {
+                        Ok(id) => id,
+                        Err(err) => return Err(ServerError::turbo_error(err)),
+                    };
                     match session_manager_guard.get_session(&session_id).await {
                         Some(session) => session,
                         None => {
@@ -76,10 +69,10 @@ where
                     }
                 };
 
-                handle_proof_execute::<PublicState, PrivateState, GameAction>(session, client, elf)
+                handle_proof_execute::<G>(session, client, elf)
                     .await
                     .map(|reply| warp::reply::json(&reply))
-                    .map_err(|e| ServerError::bad_request(e.to_string()))
+                    .map_err(ServerError::turbo_error)
             }
         });
 
@@ -91,7 +84,7 @@ where
     let prove_route = warp::path!("prove" / String)
         .and(warp::post())
         .and(warp::body::json())
-        .and_then(move |proof_type: String, actions: serde_json::Value| {
+        .and_then(move |proof_type: String, request: serde_json::Value| {
             let client = prove_client.clone();
             let elf = prove_elf.clone();
             let queue = prove_queue.clone();
@@ -99,12 +92,9 @@ where
             let tx_jobs = prove_tx_jobs.clone();
 
             async move {
-                let proof_type = match proof_type.as_str() {
-                    "core" => ProofType::Core,
-                    "compressed" => ProofType::Compressed,
-                    "groth16" => ProofType::Groth16,
-                    "plonk" => ProofType::Plonk,
-                    _ => return Err(ServerError::bad_request("Invalid proof type".into())),
+                let proof_type = match ProofType::from_name(&proof_type) {
+                    Some(proof_type) => proof_type,
+                    None => return Err(ServerError::bad_request("Invalid proof type".into())),
                 };
 
                 // Create a new task in the queue
@@ -114,11 +104,11 @@ where
                 // Build a session instance
                 let mut session_manager_guard = session_manager.lock().await;
                 let session_id_result =
-                    create_session_json(&mut session_manager_guard, reducer, actions).await;
+                    create_session_json(&mut session_manager_guard, request).await;
 
                 if let Err(err) = session_id_result {
                     queue.set_status(&task_id_clone, ProveStatus::Error(err.to_string()));
-                    return Err(ServerError::bad_request(err.to_string()));
+                    return Err(ServerError::turbo_error(err));
                 }
 
                 let session_id = session_id_result.unwrap();
@@ -167,6 +157,10 @@ where
                 async move {
                     match queue.get_status(&task_id) {
                         Some(status) => match status {
+                            ProveStatus::WaitingForGameEnd => Ok(warp::reply::json(&json!({
+                                "proof_id": task_id,
+                                "status": "waiting"
+                            }))),
                             ProveStatus::Queued => Ok(warp::reply::json(&json!({
                                 "proof_id": task_id,
                                 "status": "queued"
@@ -190,6 +184,7 @@ where
     // Add a WebSocket route for processing commands
     let ws_client = client_arc.clone();
     let ws_elf = elf_arc.clone();
+    let ws_aggregation_elf = aggregation_elf_arc.clone();
     let ws_prove_queue = prove_queue_arc.clone();
     let ws_session_manager = session_manager_arc.clone();
     let ws_tx_jobs = tx_jobs_arc.clone();
@@ -200,17 +195,18 @@ where
             let prove_queue = ws_prove_queue.clone();
             let client = ws_client.clone();
             let elf = ws_elf.clone();
+            let aggregation_elf = ws_aggregation_elf.clone();
             let tx_jobs = ws_tx_jobs.clone();
             
             async move {
                 Ok::<_, warp::reject::Rejection>(ws.on_upgrade(move |websocket| async move {
                     let (mut tx, mut rx) = websocket.split();
                     let session_manager = session_manager.clone();
-                    let mut active_session: Option<Arc<Mutex<TurboSession<PublicState, PrivateState, GameAction>>>> = None;
+                    let mut active_session: Option<Arc<Mutex<TurboSession<G>>>> = None;
                     let mut active_proof_id: Option<String> = None;
                     let mut active_player_idx: Option<usize> = None;
 
-                    if let Err(_) = tx.send(warp::ws::Message::text("{\"__state\":\"waiting\"}")).await {
+                    if tx.send(warp::ws::Message::text("{\"__state\":\"waiting\"}")).await.is_err() {
                         return;
                     }
 
@@ -226,63 +222,208 @@ where
                                                 let syscall = command.get("__syscall").unwrap().as_str().unwrap();
                                                 let mut response: Option<Value> = None;
 
-                                                if syscall == "join_session" {
+                                                if syscall == "create_session" {
+                                                    // Lets a client learn the seed commitment before choosing its own seed
+                                                    let mut session_manager_guard = session_manager.lock().await;
+                                                    response = Some(match parse_config::<G>(command.get("config")) {
+                                                        Ok(config) => {
+                                                            let session_id = session_manager_guard.create_session(config).await;
+                                                            let session = session_manager_guard.get_session(&session_id).await.unwrap();
+                                                            let seed_commitment = session.lock().await.seed_commitment();
+                                                            json!({
+                                                                "__session_id": session_id,
+                                                                "__seed_commitment": format!("0x{}", hex::encode(seed_commitment)),
+                                                            })
+                                                        }
+                                                        Err(e) => json!({
+                                                            "error": e.to_string(),
+                                                            "code": e.kind(),
+                                                        }),
+                                                    });
+                                                } else if syscall == "join_session" {
                                                     let session_id_option = command.get("session_id");
                                                     let session_id = match session_id_option {
                                                         Some(session_id) => session_id.as_str().unwrap(),
-                                                        None => &session_manager.lock().await.create_session(reducer).await
+                                                        None => &session_manager.lock().await.create_session(G::Config::default()).await
                                                     };
                                                     
                                                     let session_option = session_manager.lock().await.get_session(session_id).await;
                                                             
-                                                    if session_option.is_some() {
-                                                        let session = session_option.unwrap();
-                                                        let player_idx = session.lock().await.join_random();
+                                                    if let Some(session) = session_option {
+                                                        let player_metadata = parse_player_metadata(&command);
+                                                        let auto_prove = match command.get("auto_prove") {
+                                                            Some(proof_type) => proof_type.as_str().and_then(ProofType::from_name).map(Some).ok_or(TurboError::InvalidAction("Invalid proof type")),
+                                                            None => Ok(None),
+                                                        };
+                                                        let (joined, seed_commitment, auto_proof_id) = {
+                                                            let mut session_guard = session.lock().await;
+                                                            let joined = player_metadata.and_then(|player_metadata| {
+                                                                let auto_prove = auto_prove?;
+                                                                session_guard.join(player_metadata).map(|player_idx| (player_idx, auto_prove))
+                                                            });
 
-                                                        active_session = Some(session);
-                                                        active_player_idx = Some(player_idx);
+                                                            // Prove the finished game without waiting for a proof syscall, whoever's action ends it.
+                                                            // One proof per session, later players asking for it share the first one.
+                                                            let auto_proof_id = match &joined {
+                                                                Ok((_, Some(proof_type))) => Some(match session_guard.auto_proof_id() {
+                                                                    Some(proof_id) => proof_id.to_string(),
+                                                                    None => {
+                                                                        let proof_id = prove_queue.enqueue_waiting_task();
+                                                                        let finished_session = Arc::downgrade(&session);
+                                                                        let (proof_type, proof_id_clone, prove_queue, client, elf, tx_jobs) = (proof_type.clone(), proof_id.clone(), prove_queue.clone(), client.clone(), elf.clone(), tx_jobs.clone());
+                                                                        session_guard.on_finish(move || {
+                                                                            let Some(session) = finished_session.upgrade() else {
+                                                                                return;
+                                                                            };
+                                                                            prove_queue.set_status(&proof_id_clone, ProveStatus::Queued);
+                                                                            let request = ProofRequest::new(session, proof_type, client, elf);
+                                                                            if tx_jobs.send((proof_id_clone.clone(), request)).is_err() {
+                                                                                prove_queue.set_status(&proof_id_clone, ProveStatus::Error("Error starting proof job".into()));
+                                                                            }
+                                                                        });
+                                                                        session_guard.set_auto_proof_id(proof_id.clone());
+                                                                        proof_id
+                                                                    }
+                                                                }),
+                                                                _ => None,
+                                                            };
+                                                            (joined, session_guard.seed_commitment(), auto_proof_id)
+                                                        };
 
-                                                        response = Some(json!({
-                                                            "__state": "ready",
-                                                            "__session_id": session_id.to_string(),
-                                                        }));
+                                                        match joined {
+                                                            Ok((player_idx, _)) => {
+                                                                active_session = Some(session);
+                                                                active_player_idx = Some(player_idx);
+                                                                let mut ready = json!({
+                                                                    "__state": "ready",
+                                                                    "__session_id": session_id.to_string(),
+                                                                    "__seed_commitment": format!("0x{}", hex::encode(seed_commitment)),
+                                                                });
+                                                                if let Some(proof_id) = auto_proof_id {
+                                                                    ready["__proof_id"] = json!(proof_id);
+                                                                    active_proof_id = Some(proof_id);
+                                                                }
+                                                                response = Some(ready);
+                                                            }
+                                                            Err(e) => {
+                                                                response = Some(json!({
+                                                                    "error": e.to_string(),
+                                                                    "code": e.kind(),
+                                                                }));
+                                                            }
+                                                        }
                                                     } else {
                                                         response = Some(json!({
                                                             "error": "Failed to create session"
                                                         }));
                                                     }
-                                                } else if syscall == "proof" {
-                                                    let proof_type = command.get("proof_type").unwrap().as_str().unwrap();
-                                                    let proof_type = match proof_type {
-                                                        "core" => ProofType::Core,
-                                                        "compressed" => ProofType::Compressed,
-                                                        "groth16" => ProofType::Groth16,
-                                                        "plonk" => ProofType::Plonk,
-                                                        _ => panic!("Invalid proof type"),
+                                                } else if syscall == "leave_session" {
+                                                    let left = match (active_session.as_ref(), active_player_idx) {
+                                                        (Some(session), Some(player_idx)) => session.lock().await.leave(player_idx),
+                                                        _ => Err(TurboError::SessionNotFound),
                                                     };
-                                    
-                                                    // Create a new task in the queue
-                                                    let proof_id = prove_queue.enqueue_task();
-                                                    let proof_id_clone = proof_id.clone();
-
-                                                    // Set active proof id
-                                                    active_proof_id = Some(proof_id.clone());
-
-                                                    // Start a new proof job
-                                                    tx_jobs
-                                                        .send((
-                                                            proof_id_clone,
-                                                            ProofRequest::new(
-                                                                active_session.clone().unwrap().clone(),
+
+                                                    response = Some(match left {
+                                                        Ok(()) => json!({ "__state": "left" }),
+                                                        Err(e) => json!({
+                                                            "error": e.to_string(),
+                                                            "code": e.kind(),
+                                                        }),
+                                                    });
+                                                } else if syscall == "proof" {
+                                                    let proof_type = command.get("proof_type").and_then(|proof_type| proof_type.as_str()).and_then(ProofType::from_name);
+                                                    response = Some(match (active_session.clone(), proof_type) {
+                                                        (Some(session), Some(proof_type)) => {
+                                                            // Create a new task in the queue
+                                                            let proof_id = prove_queue.enqueue_task();
+                                                            let proof_id_clone = proof_id.clone();
+
+                                                            // Set active proof id
+                                                            active_proof_id = Some(proof_id.clone());
+
+                                                            let mut request = ProofRequest::new(
+                                                                session,
                                                                 proof_type,
                                                                 client.clone(),
                                                                 elf.clone(),
-                                                            ),
-                                                        )).unwrap();
+                                                            );
+                                                            let aggregate = command.get("aggregate").and_then(|a| a.as_bool()).unwrap_or(false);
+                                                            if let (true, Some(aggregation_elf)) = (aggregate, aggregation_elf.clone()) {
+                                                                request = request.aggregated(aggregation_elf);
+                                                            }
 
-                                                    response = Some(json!({
-                                                        "proof_id": proof_id.clone(),
-                                                    }));
+                                                            // Start a new proof job
+                                                            match tx_jobs.send((proof_id_clone, request)) {
+                                                                Ok(()) => json!({
+                                                                    "proof_id": proof_id.clone(),
+                                                                }),
+                                                                Err(_) => {
+                                                                    prove_queue.set_status(&proof_id, ProveStatus::Error("Error starting proof job".into()));
+                                                                    json!({
+                                                                        "error": "Error starting proof job"
+                                                                    })
+                                                                }
+                                                            }
+                                                        }
+                                                        (None, _) => json!({
+                                                            "error": TurboError::SessionNotFound.to_string(),
+                                                            "code": TurboError::SessionNotFound.kind(),
+                                                        }),
+                                                        (_, None) => json!({
+                                                            "error": "Invalid proof type"
+                                                        }),
+                                                    });
+                                                } else if syscall == "reveal_seed" {
+                                                    // Only a finished game, a player must not close the session for everyone
+                                                    let reveal = match active_session.as_ref() {
+                                                        Some(session) => {
+                                                            let mut session = session.lock().await;
+                                                            if session.is_finished() {
+                                                                Ok(session.reveal_server_seed())
+                                                            } else {
+                                                                Err(TurboError::SessionNotFinished)
+                                                            }
+                                                        }
+                                                        None => Err(TurboError::SessionNotFound),
+                                                    };
+                                                    response = Some(match reveal {
+                                                        Ok(reveal) => json!({
+                                                            "server_seed": reveal.seed,
+                                                            "seed_commitment": format!("0x{}", hex::encode(reveal.commitment)),
+                                                        }),
+                                                        Err(e) => json!({
+                                                            "error": e.to_string(),
+                                                            "code": e.kind(),
+                                                        }),
+                                                    });
+                                                } else if syscall == "reveal_private_state" {
+                                                    // Hidden state of a finished game, checked against `private_state_commitment`
+                                                    let reveal = match active_session.as_ref() {
+                                                        Some(session) => session.lock().await.reveal_private_state(),
+                                                        None => Err(TurboError::SessionNotFound),
+                                                    };
+                                                    response = Some(match reveal {
+                                                        Ok(reveal) => json!({
+                                                            "private_state": format!("0x{}", hex::encode(reveal.private_state)),
+                                                            "salt": format!("0x{}", hex::encode(reveal.salt)),
+                                                            "private_state_commitment": format!("0x{}", hex::encode(reveal.commitment)),
+                                                        }),
+                                                        Err(e) => json!({
+                                                            "error": e.to_string(),
+                                                            "code": e.kind(),
+                                                        }),
+                                                    });
+                                                } else if syscall == "checkpoint" {
+                                                    // Later proofs continue from here instead of replaying the whole session
+                                                    response = Some(match active_session.as_ref() {
+                                                        Some(session) => json!({
+                                                            "state_hash": format!("0x{}", hex::encode(session.lock().await.checkpoint())),
+                                                        }),
+                                                        None => json!({
+                                                            "error": TurboError::SessionNotFound.to_string(),
+                                                            "code": TurboError::SessionNotFound.kind(),
+                                                        }),
+                                                    });
                                                 } else if syscall == "proof_status" {
                                                     let proof_id_option = command.get("proof_id");
                                                     let proof_id: String = match proof_id_option {
@@ -297,6 +438,10 @@ where
                                                     };
                                                     
                                                     response = match status {
+                                                        ProveStatus::WaitingForGameEnd => Some(json!({
+                                                            "proof_id": proof_id,
+                                                            "status": "waiting"
+                                                        })),
                                                         ProveStatus::Queued => Some(json!({
                                                             "proof_id": proof_id,
                                                             "status": "queued"
@@ -323,25 +468,30 @@ where
                                                     "error": "Syscalls not yet implemented"
                                                 }))).unwrap_or_else(|_| String::from("{\"error\":\"Failed to serialize response\"}"))
                                             } else {
-                                                let player_idx = active_player_idx.unwrap();
-                                                let result = dispatch_actions(active_session.clone().unwrap(), command, player_idx).await;
-
-                                                if let Err(e) = result {
-                                                    serde_json::to_string(&json!({
-                                                        "error": e
-                                                    })).unwrap_or_else(|_| String::from("{\"error\":\"Failed to serialize response\"}"))
-                                                } else {
-                                                    let result_json = active_session.clone().unwrap().lock().await.serialize_json(player_idx).unwrap();
-                                                    serde_json::to_string(&result_json).unwrap_or_else(|_| String::from("{\"error\":\"Failed to serialize response\"}"))
-                                                }
+                                                // Only the player this connection joined as, never on behalf of anyone else
+                                                let result_json = match (active_session.clone(), active_player_idx) {
+                                                    (Some(session), Some(player_idx)) => match dispatch_actions(session.clone(), command, player_idx).await {
+                                                        Ok(()) => session.lock().await.serialize_json(player_idx),
+                                                        Err(e) => Err(e),
+                                                    },
+                                                    _ => Err(TurboError::SessionNotFound),
+                                                };
+                                                let result_json = match result_json {
+                                                    Ok(result_json) => result_json,
+                                                    Err(e) => json!({
+                                                        "error": e.to_string(),
+                                                        "code": e.kind()
+                                                    }),
+                                                };
+                                                serde_json::to_string(&result_json).unwrap_or_else(|_| String::from("{\"error\":\"Failed to serialize response\"}"))
                                             };
 
-                                            if let Err(_) = tx.send(warp::ws::Message::text(response)).await {
+                                            if tx.send(warp::ws::Message::text(response)).await.is_err() {
                                                 break;
                                             }
                                         },
                                         Err(_) => {
-                                            if let Err(_) = tx.send(warp::ws::Message::text("{\"error\":\"Invalid JSON\"}")).await {
+                                            if tx.send(warp::ws::Message::text("{\"error\":\"Invalid JSON\"}")).await.is_err() {
                                                 break;
                                             }
                                         }
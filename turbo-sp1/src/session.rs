@@ -1,62 +1,116 @@
-use std::panic::{self, AssertUnwindSafe};
+use std::{
+    any::Any,
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use rand::thread_rng;
-use serde::Serialize;
 use serde_json::{json, Value};
 use sp1_sdk::SP1Stdin;
 use substrate_bn::*;
 use turbo_program::{
-    context::{TurboActionContext, TurboActionContextInner},
-    crypto::bn_serialize::bn254_export_affine_g1_memcpy,
+    checkpoint::{checkpoint_hash, TurboCheckpoint},
+    context::{
+        TurboActionContext, TurboActionContextInner, TurboSystemContext, TurboSystemContextInner,
+    },
+    crypto::{
+        bn_serialize::bn254_export_affine_g1_memcpy,
+        signature::{action_signing_message, split_signature, PlayerPublicKey},
+    },
+    error::TurboError,
+    framing::{
+        action_hash_input, encode_actions, stamp_action, ActionSender, SYSTEM_DEADLINE,
+        SYSTEM_GAME_ACTION, SYSTEM_JOIN, SYSTEM_LEAVE,
+    },
+    game::{TurboGame, TurboOutput, TurboPlayerView},
     metadata::{PlayerMetadata, ServerMetadata},
-    program::TurboReducer,
-    traits::TurboActionSerialization,
+    private_state::PrivateStateReveal,
+    rand::provably_fair::ServerSeedReveal,
+    traits::{TurboActionField, TurboActionSerialization},
+    transcript::config_hash,
+    view::encrypted_player_views,
 };
 use uuid::Uuid;
 
-pub struct TurboSession<PublicState, PrivateState, GameAction>
-where
-    PublicState: Serialize + Default + Send + Sync,
-    PrivateState: Default + Send + Sync,
-    GameAction: TurboActionSerialization + Send + Sync,
-{
+// Run once when the session finishes, see `TurboSession::on_finish`
+type FinishHook = Box<dyn FnOnce() + Send + Sync>;
+
+/// A proven part of a session: the actions between two checkpoints.
+#[derive(Debug, Clone)]
+struct TurboSegment {
+    checkpoint: Option<Vec<u8>>,
+    actions: Range<usize>,
+}
+
+pub struct TurboSession<G: TurboGame> {
     id: String,
     actions: Vec<u8>,
     server_metadata: ServerMetadata,
     player_metadata: Vec<PlayerMetadata>,
-    contexts: Vec<TurboActionContextInner>,
+    contexts: Vec<TurboActionContextInner<G::ActionHasher>>,
+    system: TurboSystemContextInner<G::ActionHasher>,
 
-    reducer: TurboReducer<PublicState, PrivateState, GameAction>,
-    public_state: PublicState,
-    private_state: PrivateState,
+    config: G::Config,
+    initial_player_count: usize,
+    public_state: G::PublicState,
+    private_state: G::PrivateState,
+
+    // Checkpoint the next proof continues from, and the offset of the first action after it
+    checkpoint: Option<Vec<u8>>,
+    checkpoint_offset: usize,
+    segments: Vec<TurboSegment>,
 
     is_bricked: bool,
+    is_revealed: bool,
+    is_finished: bool,
+    finish_hooks: Vec<FinishHook>,
+    auto_proof_id: Option<String>,
+
+    // Encrypted views are derived from it, unlike the server seed it is never revealed
+    view_secret: [u8; 32],
 }
 
-impl<
-        PublicState: Serialize + Default + Send + Sync,
-        PrivateState: Default + Send + Sync,
-        GameAction: TurboActionSerialization + Send + Sync,
-    > TurboSession<PublicState, PrivateState, GameAction>
-{
-    pub fn new(reducer: TurboReducer<PublicState, PrivateState, GameAction>) -> Self {
-        let id = Uuid::new_v4().to_string();
+impl<G: TurboGame> TurboSession<G> {
+    pub fn new(config: G::Config) -> Self {
         let mut rng = thread_rng();
-
         let server_random_seed = AffineG1::one() * Fr::random(&mut rng);
 
+        Self::with_server_metadata(
+            config,
+            ServerMetadata {
+                random_seed: bn254_export_affine_g1_memcpy(&server_random_seed),
+            },
+        )
+    }
+
+    /// Create a session with a known server seed, e.g. to replay a recorded transcript.
+    pub fn with_server_metadata(config: G::Config, server_metadata: ServerMetadata) -> Self {
+        let id = Uuid::new_v4().to_string();
+        let (public_state, private_state) = G::init(&config, &[]);
+        let is_finished = G::is_terminal(&public_state, &private_state);
+        let system = TurboSystemContextInner::new(&server_metadata);
+
         Self {
             id,
             actions: Vec::new(),
-            server_metadata: ServerMetadata {
-                random_seed: bn254_export_affine_g1_memcpy(&server_random_seed),
-            },
+            server_metadata,
             player_metadata: Vec::new(),
             contexts: Vec::new(),
-            reducer,
-            public_state: PublicState::default(),
-            private_state: PrivateState::default(),
+            system,
+            config,
+            initial_player_count: 0,
+            public_state,
+            private_state,
+            checkpoint: None,
+            checkpoint_offset: 0,
+            segments: Vec::new(),
             is_bricked: false,
+            is_revealed: false,
+            is_finished,
+            finish_hooks: Vec::new(),
+            auto_proof_id: None,
+            view_secret: rand::random(),
         }
     }
 
@@ -64,93 +118,704 @@ impl<
         self.id.clone()
     }
 
+    pub fn server_metadata(&self) -> &ServerMetadata {
+        &self.server_metadata
+    }
+
     pub fn actions(&self) -> &Vec<u8> {
         &self.actions
     }
 
+    /// Actions after the last checkpoint, the ones proven by the next proof.
+    pub fn pending_actions(&self) -> &[u8] {
+        &self.actions[self.checkpoint_offset..]
+    }
+
+    /// Every player that joined, including the ones that left.
+    pub fn player_metadata(&self) -> &[PlayerMetadata] {
+        &self.player_metadata
+    }
+
     pub fn player_count(&self) -> usize {
         self.player_metadata.len()
     }
 
-    pub fn join(&mut self, player_metadata: PlayerMetadata) -> usize {
-        self.player_metadata.push(player_metadata);
+    /// Players present before the first action, passed to `TurboGame::init` and to the program.
+    pub fn initial_player_metadata(&self) -> &[PlayerMetadata] {
+        &self.player_metadata[..self.initial_player_count]
+    }
 
-        let player_idx = self.player_metadata.len() - 1;
+    /// Add a player. Until the first action every player is part of the initial state passed to
+    /// `init`, later players join with a system action and `TurboGame::on_join`.
+    pub fn join(&mut self, player_metadata: PlayerMetadata) -> Result<usize, TurboError> {
+        self.ensure_open()?;
+        // A key nobody can sign for would lock the player out, and views could not be encrypted to it
+        if let Some(public_key) = &player_metadata.public_key {
+            public_key.validate()?;
+        }
 
-        let context = TurboActionContextInner::new(
-            &self.server_metadata,
-            &self.player_metadata[player_idx],
-            player_idx,
-        );
+        // The game starts with the first action, until then every join is part of the initial state
+        if self.actions.is_empty() && self.checkpoint.is_none() {
+            let player_idx = self.player_metadata.len();
+            self.contexts.push(TurboActionContextInner::new(
+                &self.server_metadata,
+                &player_metadata,
+                player_idx,
+            ));
+            self.player_metadata.push(player_metadata);
 
-        self.contexts.push(context);
+            let (public_state, private_state) = G::init(&self.config, &self.player_metadata);
+            self.public_state = public_state;
+            self.private_state = private_state;
+            self.update_finished();
+            self.initial_player_count = self.player_metadata.len();
+            return Ok(player_idx);
+        }
 
-        player_idx
+        let mut action_raw = ActionSender::System(SYSTEM_JOIN).to_bytes()?;
+        player_metadata.random_seed.encode_field(&mut action_raw);
+        PlayerPublicKey::encode_optional(player_metadata.public_key.as_ref(), &mut action_raw);
+        self.dispatch(&action_raw)?;
+
+        Ok(self.player_metadata.len() - 1)
     }
 
-    pub fn join_random(&mut self) -> usize {
-        let mut rng = thread_rng();
-        let player_random_seed = AffineG1::one() * Fr::random(&mut rng);
-        let player_metadata = PlayerMetadata {
-            random_seed: bn254_export_affine_g1_memcpy(&player_random_seed),
+    pub fn join_random(&mut self) -> Result<usize, TurboError> {
+        self.join(PlayerMetadata::new(random_player_seed()))
+    }
+
+    /// Remove a player with a system action and `TurboGame::on_leave`, the player index is kept
+    /// and the player's later actions are refused.
+    pub fn leave(&mut self, player_idx: usize) -> Result<(), TurboError> {
+        let mut action_raw = ActionSender::System(SYSTEM_LEAVE).to_bytes()?;
+        u32::try_from(player_idx)
+            .map_err(|_| TurboError::UnknownPlayer(player_idx))?
+            .encode_field(&mut action_raw);
+        self.dispatch(&action_raw)
+    }
+
+    /// Whether the player joined and has not left.
+    pub fn is_active(&self, player_idx: usize) -> bool {
+        self.contexts
+            .get(player_idx)
+            .is_some_and(|context| context.is_active())
+    }
+
+    fn ensure_open(&self) -> Result<(), TurboError> {
+        if self.is_bricked {
+            return Err(TurboError::SessionBricked);
+        }
+        if self.is_revealed {
+            return Err(TurboError::SeedRevealed);
+        }
+        if self.is_finished {
+            return Err(TurboError::SessionFinished);
+        }
+        Ok(())
+    }
+
+    /// Apply an action from a player or the server (`framing` without the timestamp), stamped
+    /// with the current time.
+    pub fn dispatch(&mut self, action_raw: &[u8]) -> Result<(), TurboError> {
+        self.dispatch_at(action_raw, self.now())
+    }
+
+    // The clock may step back, timestamps in the action stream never do
+    fn now(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        now.max(self.system.timestamp())
+    }
+
+    /// Apply an action stamped with `timestamp`, e.g. to replay a recorded transcript.
+    ///
+    /// Timestamps are in milliseconds since the Unix epoch and must not be older than the
+    /// previous action.
+    pub fn dispatch_at(&mut self, action_raw: &[u8], timestamp: u64) -> Result<(), TurboError> {
+        self.ensure_open()?;
+
+        if timestamp < self.system.timestamp() {
+            return Err(TurboError::InvalidAction(
+                "Timestamp before the previous action",
+            ));
+        }
+
+        let (sender, action_bytes) = ActionSender::decode(action_raw)?;
+        let frame = stamp_action(action_raw, timestamp)?;
+        let result = match sender {
+            ActionSender::Player(player_idx) => {
+                self.dispatch_player_raw(&frame, action_bytes, timestamp, player_idx)
+            }
+            ActionSender::System(SYSTEM_GAME_ACTION) => {
+                self.dispatch_system_raw(&frame, action_bytes, timestamp)
+            }
+            ActionSender::System(SYSTEM_JOIN) => {
+                self.dispatch_join_raw(&frame, action_bytes, timestamp)
+            }
+            ActionSender::System(SYSTEM_LEAVE) => {
+                self.dispatch_leave_raw(&frame, action_bytes, timestamp)
+            }
+            ActionSender::System(SYSTEM_DEADLINE) => {
+                self.dispatch_deadline_raw(&frame, action_bytes, timestamp)
+            }
+            ActionSender::System(_) => Err(TurboError::InvalidAction("Unknown system action")),
         };
-        self.join(player_metadata)
+
+        // Decode errors are relative to the action bytes, report them relative to `action_raw`
+        result.map_err(|e| e.at_offset(action_raw.len() - action_bytes.len()))?;
+
+        // The terminal state closes the session, the proof refuses any action after it
+        self.update_finished();
+        Ok(())
     }
 
-    pub fn dispatch(&mut self, action_raw: &[u8]) -> Result<(), &'static str> {
-        let (action, next_actions) = GameAction::deserialize(&action_raw[1..])?;
-        let player_idx = action_raw[0] as usize;
+    fn update_finished(&mut self) {
+        self.is_finished = self.is_terminal();
+        if self.is_finished {
+            for hook in self.finish_hooks.drain(..) {
+                hook();
+            }
+        }
+    }
+
+    /// Apply an action from a player, routed to `TurboGame::reduce`. `frame` is the stamped action
+    /// stored in the action stream.
+    fn dispatch_player_raw(
+        &mut self,
+        frame: &[u8],
+        action_bytes: &[u8],
+        timestamp: u64,
+        player_idx: usize,
+    ) -> Result<(), TurboError> {
+        let (action, mut next_actions) = G::Action::deserialize(action_bytes)?;
+        let action_bytes = &action_bytes[..action_bytes.len() - next_actions.len()];
+
+        if player_idx >= self.player_count() {
+            return Err(TurboError::UnknownPlayer(player_idx));
+        }
+        if !self.contexts[player_idx].is_active() {
+            return Err(TurboError::PlayerLeft(player_idx));
+        }
+
+        let inner = self.contexts[player_idx].clone();
+        if let Some(public_key) = &self.player_metadata[player_idx].public_key {
+            let (signature, rest) =
+                split_signature(next_actions).map_err(|e| e.at_offset(action_bytes.len()))?;
+            let message = action_signing_message(
+                &self.server_metadata.seed_commitment(),
+                inner.action_sequence(),
+                action_bytes,
+            );
+            if !public_key.verify(&message, signature) {
+                return Err(TurboError::InvalidSignature(player_idx));
+            }
+            next_actions = rest;
+        }
 
         if !next_actions.is_empty() {
-            return Err("Dispatching multiple actions is not allowed");
+            return Err(TurboError::InvalidAction(
+                "Dispatching multiple actions is not allowed",
+            ));
         }
 
         let mut context = TurboActionContext::new_from_inner(
             &self.server_metadata,
             &self.player_metadata[player_idx],
-            self.contexts[player_idx].clone(),
+            inner,
+        )
+        .with_timestamp(timestamp)
+        .with_config(&self.config);
+        context.update_action_hash(&action_hash_input(timestamp, action_bytes));
+
+        // The reducer runs on a copy so a rejected action cannot leave partial changes behind
+        let mut public_state = self.public_state.clone();
+        let mut private_state = self.private_state.clone();
+        let result = catch_reducer_panic(|| {
+            G::reduce(&mut public_state, &mut private_state, &action, &mut context)
+        });
+
+        match result {
+            Ok(Ok(())) => {}
+            // Rejected actions are skipped, the stored state and context are left untouched
+            Ok(Err(e)) => return Err(e.into()),
+            Err(e) => {
+                self.is_bricked = true;
+                return Err(e);
+            }
+        }
+
+        // Only accepted actions use up a signature sequence number, as in the program
+        context.inner.advance_action_sequence();
+        self.public_state = public_state;
+        self.private_state = private_state;
+        self.actions.extend(frame);
+        self.contexts[player_idx] = context.inner;
+        self.system.advance(timestamp);
+
+        Ok(())
+    }
+
+    /// Apply an action from the server, routed to `TurboGame::reduce_system`.
+    pub fn dispatch_system(&mut self, action: &G::SystemAction) -> Result<(), TurboError> {
+        let mut action_raw = ActionSender::System(SYSTEM_GAME_ACTION).to_bytes()?;
+        action_raw.extend(action.serialize());
+        self.dispatch(&action_raw)
+    }
+
+    fn dispatch_system_raw(
+        &mut self,
+        frame: &[u8],
+        action_bytes: &[u8],
+        timestamp: u64,
+    ) -> Result<(), TurboError> {
+        let (action, next_actions) = G::SystemAction::deserialize(action_bytes)?;
+
+        if !next_actions.is_empty() {
+            return Err(TurboError::InvalidAction(
+                "Dispatching multiple actions is not allowed",
+            ));
+        }
+
+        let mut context = TurboSystemContext::new_from_inner(
+            &self.server_metadata,
+            &self.player_metadata,
+            self.system.clone(),
+        )
+        .with_timestamp(timestamp)
+        .with_config(&self.config);
+        context.update_action_hash(frame);
+
+        let mut public_state = self.public_state.clone();
+        let mut private_state = self.private_state.clone();
+        let result = catch_reducer_panic(|| {
+            G::reduce_system(&mut public_state, &mut private_state, &action, &mut context)
+        });
+
+        match result {
+            Ok(Ok(())) => {}
+            // Rejected actions are skipped, the stored state and context are left untouched
+            Ok(Err(e)) => return Err(e.into()),
+            Err(e) => {
+                self.is_bricked = true;
+                return Err(e);
+            }
+        }
+
+        context.inner.advance_tick();
+        context.inner.advance(timestamp);
+        self.public_state = public_state;
+        self.private_state = private_state;
+        self.actions.extend(frame);
+        self.system = context.inner;
+
+        Ok(())
+    }
+
+    fn dispatch_join_raw(
+        &mut self,
+        frame: &[u8],
+        action_bytes: &[u8],
+        timestamp: u64,
+    ) -> Result<(), TurboError> {
+        let (random_seed, key_bytes) = <[u32; 16]>::decode_field(action_bytes)?;
+        let (public_key, next_actions) = PlayerPublicKey::decode_optional(key_bytes)
+            .map_err(|e| e.at_offset(action_bytes.len() - key_bytes.len()))?;
+
+        if !next_actions.is_empty() {
+            return Err(TurboError::InvalidAction(
+                "Dispatching multiple actions is not allowed",
+            ));
+        }
+        if let Some(public_key) = &public_key {
+            public_key.validate()?;
+        }
+
+        let player_idx = self.player_metadata.len();
+        self.player_metadata.push(PlayerMetadata {
+            random_seed,
+            public_key,
+        });
+        let mut player = TurboActionContextInner::new(
+            &self.server_metadata,
+            &self.player_metadata[player_idx],
+            player_idx,
         );
-        let result = panic::catch_unwind(AssertUnwindSafe(|| {
-            (self.reducer)(
+        player.mark_joined(self.system.sequence());
+        self.contexts.push(player);
+
+        self.dispatch_system_event(frame, timestamp, |public_state, private_state, context| {
+            G::on_join(public_state, private_state, player_idx, context)
+        })
+    }
+
+    fn dispatch_leave_raw(
+        &mut self,
+        frame: &[u8],
+        action_bytes: &[u8],
+        timestamp: u64,
+    ) -> Result<(), TurboError> {
+        let (player_idx, next_actions) = u32::decode_field(action_bytes)?;
+        let player_idx = player_idx as usize;
+
+        if !next_actions.is_empty() {
+            return Err(TurboError::InvalidAction(
+                "Dispatching multiple actions is not allowed",
+            ));
+        }
+
+        if player_idx >= self.player_count() {
+            return Err(TurboError::UnknownPlayer(player_idx));
+        }
+        if !self.contexts[player_idx].is_active() {
+            return Err(TurboError::PlayerLeft(player_idx));
+        }
+        self.contexts[player_idx].mark_left(self.system.sequence());
+
+        self.dispatch_system_event(frame, timestamp, |public_state, private_state, context| {
+            G::on_leave(public_state, private_state, player_idx, context)
+        })
+    }
+
+    /// Dispatch the deadline action if `TurboGame::deadline` passed at the current time, returning
+    /// whether it was dispatched. Call it before applying player actions so late actions see the
+    /// penalties.
+    pub fn enforce_deadline(&mut self) -> Result<bool, TurboError> {
+        self.enforce_deadline_at(self.now())
+    }
+
+    /// `enforce_deadline` at `timestamp`, e.g. to replay a recorded transcript.
+    pub fn enforce_deadline_at(&mut self, timestamp: u64) -> Result<bool, TurboError> {
+        if self.is_finished {
+            return Ok(false);
+        }
+        match G::deadline(&self.public_state, &self.private_state) {
+            Some(deadline) if timestamp >= deadline => {
+                self.dispatch_at(
+                    &ActionSender::System(SYSTEM_DEADLINE).to_bytes()?,
+                    timestamp,
+                )?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn dispatch_deadline_raw(
+        &mut self,
+        frame: &[u8],
+        action_bytes: &[u8],
+        timestamp: u64,
+    ) -> Result<(), TurboError> {
+        if !action_bytes.is_empty() {
+            return Err(TurboError::InvalidAction(
+                "Dispatching multiple actions is not allowed",
+            ));
+        }
+
+        // The proof refuses a deadline action stamped before the deadline
+        match G::deadline(&self.public_state, &self.private_state) {
+            Some(deadline) if timestamp >= deadline => {}
+            Some(_) => return Err(TurboError::InvalidAction("Deadline not reached")),
+            None => return Err(TurboError::InvalidAction("No deadline pending")),
+        }
+
+        self.dispatch_system_event(frame, timestamp, G::on_deadline)
+    }
+
+    /// Run a hook without a result (`on_join`, `on_leave`, `on_deadline`) on the system context.
+    fn dispatch_system_event(
+        &mut self,
+        frame: &[u8],
+        timestamp: u64,
+        hook: impl FnOnce(
+            &mut G::PublicState,
+            &mut G::PrivateState,
+            &mut TurboSystemContext<G::ActionHasher, G::Config>,
+        ),
+    ) -> Result<(), TurboError> {
+        let mut context = TurboSystemContext::new_from_inner(
+            &self.server_metadata,
+            &self.player_metadata,
+            self.system.clone(),
+        )
+        .with_timestamp(timestamp)
+        .with_config(&self.config);
+        context.update_action_hash(frame);
+
+        let result = catch_reducer_panic(|| {
+            hook(
                 &mut self.public_state,
                 &mut self.private_state,
-                &action,
                 &mut context,
-            );
-        }))
-        .map_err(|_| "Failed to dispatch action");
-
+            )
+        });
         if let Err(e) = result {
             self.is_bricked = true;
             return Err(e);
         }
 
-        self.actions.extend(action_raw);
-        self.contexts[player_idx] = context.inner;
+        context.inner.advance(timestamp);
+        self.actions.extend(frame);
+        self.system = context.inner;
 
         Ok(())
     }
 
     pub fn sp1_stdin(&self) -> SP1Stdin {
+        self.segment_stdin(&self.pending_segment())
+    }
+
+    /// Stdin of every segment in order, each one continuing from the checkpoint the previous one
+    /// ends at. The pending actions form the last segment unless there are none.
+    pub fn segment_stdins(&self) -> Vec<SP1Stdin> {
+        let pending = self.pending_segment();
+        let pending = (self.segments.is_empty() || !pending.actions.is_empty()).then_some(pending);
+        self.segments
+            .iter()
+            .chain(pending.as_ref())
+            .map(|segment| self.segment_stdin(segment))
+            .collect()
+    }
+
+    fn pending_segment(&self) -> TurboSegment {
+        TurboSegment {
+            checkpoint: self.checkpoint.clone(),
+            actions: self.checkpoint_offset..self.actions.len(),
+        }
+    }
+
+    fn segment_stdin(&self, segment: &TurboSegment) -> SP1Stdin {
         let mut stdin = SP1Stdin::new();
         stdin.write(&self.server_metadata);
-        stdin.write(&self.player_metadata);
-        stdin.write(&self.actions);
+        stdin.write(&self.config);
+        stdin.write(&self.initial_player_metadata().to_vec());
+        stdin.write(&segment.checkpoint);
+        stdin.write(&encode_actions(&self.actions[segment.actions.clone()]));
+        stdin.write(&self.view_secret);
+        stdin.write(&false);
         stdin
     }
 
-    pub fn public_state(&self) -> &PublicState {
+    /// Snapshot the current state so the next proof only covers the actions after this point.
+    ///
+    /// Returns the checkpoint hash, committed as `stateHash` by a proof ending here and as
+    /// `priorStateHash` by the proof continuing from it.
+    pub fn checkpoint(&mut self) -> [u8; 32] {
+        let bytes = self.checkpoint_bytes();
+        let hash = checkpoint_hash(&bytes);
+        let segment = TurboSegment {
+            checkpoint: self.checkpoint.replace(bytes),
+            actions: self.checkpoint_offset..self.actions.len(),
+        };
+        self.segments.push(segment);
+        self.checkpoint_offset = self.actions.len();
+        hash
+    }
+
+    /// Hash of the checkpoint the next proof continues from, zero if it starts from `init`.
+    pub fn prior_state_hash(&self) -> [u8; 32] {
+        self.checkpoint
+            .as_ref()
+            .map_or([0u8; 32], |bytes| checkpoint_hash(bytes))
+    }
+
+    /// Hash of the current state, as committed by a proof of the pending actions.
+    pub fn state_hash(&self) -> [u8; 32] {
+        checkpoint_hash(&self.checkpoint_bytes())
+    }
+
+    fn checkpoint_bytes(&self) -> Vec<u8> {
+        TurboCheckpoint {
+            public_state: &self.public_state,
+            private_state: &self.private_state,
+            players: self.player_metadata.clone(),
+            contexts: self.contexts.iter().map(|c| c.checkpoint()).collect(),
+            system: self.system.checkpoint(),
+        }
+        .to_bytes()
+    }
+
+    /// Action hash of every player, as committed in `TurboPublicValues::actionHashes`.
+    pub fn action_hashes(&self) -> Vec<[u32; 8]> {
+        self.contexts.iter().map(|c| c.action_hash()).collect()
+    }
+
+    /// Hash of the system actions, as committed in `TurboPublicValues::systemActionHash`.
+    pub fn system_action_hash(&self) -> [u32; 8] {
+        self.system.action_hash()
+    }
+
+    /// Timestamp of the last accepted action, zero before the first one.
+    pub fn timestamp(&self) -> u64 {
+        self.system.timestamp()
+    }
+
+    /// Number of system actions accepted so far.
+    pub fn tick(&self) -> u64 {
+        self.system.tick()
+    }
+
+    pub fn config(&self) -> &G::Config {
+        &self.config
+    }
+
+    /// Hash of the game configuration committed in the public values.
+    pub fn config_hash(&self) -> [u8; 32] {
+        config_hash(&self.config)
+    }
+
+    pub fn public_state(&self) -> &G::PublicState {
         &self.public_state
     }
 
-    pub fn private_state(&self) -> &PrivateState {
+    pub fn private_state(&self) -> &G::PrivateState {
         &self.private_state
     }
 
-    pub fn serialize_json(&self, player_idx: usize) -> Result<Value, &'static str> {
+    /// What a proof of the session so far commits, see `TurboOutput`.
+    pub fn output(&self) -> G::Output {
+        G::Output::from_state(&self.public_state, &self.private_state)
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        G::is_terminal(&self.public_state, &self.private_state)
+    }
+
+    /// Whether the game reached its terminal state, see `TurboGame::is_terminal`.
+    pub fn is_finished(&self) -> bool {
+        self.is_finished
+    }
+
+    /// Run `hook` once the game reaches its terminal state, whichever action gets it there: any
+    /// player's action, a system action or a deadline. Runs right away if the game is already over.
+    ///
+    /// The hook runs while the caller holds the session, it must not lock it again.
+    pub fn on_finish(&mut self, hook: impl FnOnce() + Send + Sync + 'static) {
+        if self.is_finished {
+            hook();
+        } else {
+            self.finish_hooks.push(Box::new(hook));
+        }
+    }
+
+    /// Proof of the finished game registered through `set_auto_proof_id`, if any.
+    pub fn auto_proof_id(&self) -> Option<&str> {
+        self.auto_proof_id.as_deref()
+    }
+
+    /// Record the proof a finish hook starts, so a session is auto-proven at most once.
+    pub fn set_auto_proof_id(&mut self, proof_id: String) {
+        self.auto_proof_id = Some(proof_id);
+    }
+
+    pub fn is_bricked(&self) -> bool {
+        self.is_bricked
+    }
+
+    /// Commitment to the server seed, published when the session is created.
+    pub fn seed_commitment(&self) -> [u8; 32] {
+        self.server_metadata.seed_commitment()
+    }
+
+    /// Reveal the server seed so players can check it against the commitment.
+    ///
+    /// Knowing the seed makes every later random value predictable, so the session stops
+    /// accepting actions.
+    pub fn reveal_server_seed(&mut self) -> ServerSeedReveal {
+        self.is_revealed = true;
+        ServerSeedReveal::new(&self.server_metadata)
+    }
+
+    pub fn is_revealed(&self) -> bool {
+        self.is_revealed
+    }
+
+    /// Commitment to the private state, the one a proof of the session so far commits.
+    pub fn private_state_commitment(&self) -> [u8; 32] {
+        PrivateStateReveal::new(&self.private_state, &self.server_metadata).commitment
+    }
+
+    /// Reveal the private state with the salt of its commitment.
+    ///
+    /// Only once the game is over, the private state stays hidden while the game can go on, even
+    /// if the session was closed early by revealing the server seed.
+    pub fn reveal_private_state(&self) -> Result<PrivateStateReveal, TurboError> {
+        if !self.is_finished {
+            return Err(TurboError::SessionNotFinished);
+        }
+        Ok(PrivateStateReveal::new(
+            &self.private_state,
+            &self.server_metadata,
+        ))
+    }
+
+    /// What the player may see, see `TurboPlayerView`.
+    pub fn player_view(&self, player_idx: usize) -> Result<G::PlayerView, TurboError> {
+        if player_idx >= self.player_count() {
+            return Err(TurboError::UnknownPlayer(player_idx));
+        }
+        Ok(G::PlayerView::for_player(
+            &self.public_state,
+            &self.private_state,
+            player_idx,
+        ))
+    }
+
+    /// Secret the keys of the encrypted views are derived from, written to the program stdin.
+    ///
+    /// Unlike the server seed it is never revealed, it must stay on the server.
+    pub fn view_secret(&self) -> &[u8; 32] {
+        &self.view_secret
+    }
+
+    /// Encrypted views a proof of the session so far commits, empty unless the game sets
+    /// `TurboGame::ENCRYPTED_VIEWS`.
+    pub fn encrypted_player_views(&self) -> Result<Vec<Vec<u8>>, TurboError> {
+        if !G::ENCRYPTED_VIEWS {
+            return Ok(Vec::new());
+        }
+        encrypted_player_views::<G>(
+            &self.public_state,
+            &self.private_state,
+            &self.view_secret,
+            &self.player_metadata,
+            &self.state_hash(),
+        )
+    }
+
+    pub fn serialize_json(&self, player_idx: usize) -> Result<Value, TurboError> {
+        if player_idx >= self.player_count() {
+            return Err(TurboError::UnknownPlayer(player_idx));
+        }
+
+        // The player's view, the full public state unless the game hides part of it
         Ok(json!({
-            "public_state": self.public_state,
+            "public_state": self.player_view(player_idx)?,
             "client_response": self.contexts[player_idx].client_response,
+            "finished": self.is_finished,
         }))
     }
 }
+
+/// A random player seed, for players that do not bring their own.
+pub fn random_player_seed() -> [u32; 16] {
+    let mut rng = thread_rng();
+    let player_random_seed = AffineG1::one() * Fr::random(&mut rng);
+    bn254_export_affine_g1_memcpy(&player_random_seed)
+}
+
+fn catch_reducer_panic<R>(f: impl FnOnce() -> R) -> Result<R, TurboError> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .map_err(|payload| TurboError::ReducerPanic(panic_message(payload.as_ref())))
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic".to_string()
+    }
+}
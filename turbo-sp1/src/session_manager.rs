@@ -1,51 +1,30 @@
-use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use turbo_program::program::TurboReducer;
-use turbo_program::traits::TurboActionSerialization;
+use turbo_program::game::TurboGame;
 
 use crate::session::TurboSession;
 
-pub struct SessionManager<PublicState, PrivateState, GameAction>
-where
-    PublicState: Serialize + Default + Send + Sync,
-    PrivateState: Default + Send + Sync,
-    GameAction: TurboActionSerialization + Send + Sync,
-{
-    sessions:
-        Mutex<HashMap<String, Arc<Mutex<TurboSession<PublicState, PrivateState, GameAction>>>>>,
+pub struct SessionManager<G: TurboGame> {
+    sessions: Mutex<HashMap<String, Arc<Mutex<TurboSession<G>>>>>,
 }
 
-impl<
-        PublicState: Serialize + Default + Send + Sync,
-        PrivateState: Default + Send + Sync,
-        GameAction: TurboActionSerialization + Send + Sync,
-    > Default for SessionManager<PublicState, PrivateState, GameAction>
-{
+impl<G: TurboGame> Default for SessionManager<G> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<
-        PublicState: Serialize + Default + Send + Sync,
-        PrivateState: Default + Send + Sync,
-        GameAction: TurboActionSerialization + Send + Sync,
-    > SessionManager<PublicState, PrivateState, GameAction>
-{
+impl<G: TurboGame> SessionManager<G> {
     pub fn new() -> Self {
         Self {
             sessions: Mutex::new(HashMap::new()),
         }
     }
 
-    pub async fn create_session(
-        &mut self,
-        reducer: TurboReducer<PublicState, PrivateState, GameAction>,
-    ) -> String {
-        let session = TurboSession::new(reducer);
+    pub async fn create_session(&mut self, config: G::Config) -> String {
+        let session = TurboSession::new(config);
         let id = session.id();
 
         let mut sessions = self.sessions.lock().await;
@@ -53,10 +32,7 @@ impl<
         id
     }
 
-    pub async fn get_session(
-        &self,
-        id: &str,
-    ) -> Option<Arc<Mutex<TurboSession<PublicState, PrivateState, GameAction>>>> {
+    pub async fn get_session(&self, id: &str) -> Option<Arc<Mutex<TurboSession<G>>>> {
         let sessions = self.sessions.lock().await;
         sessions.get(id).cloned()
     }
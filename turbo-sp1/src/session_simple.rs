@@ -1,32 +1,48 @@
 use std::sync::Arc;
 
-use serde::Serialize;
 use tokio::sync::Mutex;
-use turbo_program::{program::TurboReducer, traits::TurboActionSerialization};
+use turbo_program::{
+    crypto::signature::{split_signature, PlayerPublicKey},
+    error::TurboError,
+    framing::ActionSender,
+    game::TurboGame,
+    metadata::PlayerMetadata,
+    traits::TurboActionSerialization,
+};
 
-use crate::{session::TurboSession, session_manager::SessionManager};
+use crate::{
+    session::{random_player_seed, TurboSession},
+    session_manager::SessionManager,
+};
 
-pub async fn dispatch_actions<PublicState, PrivateState, GameAction>(
-    session: Arc<Mutex<TurboSession<PublicState, PrivateState, GameAction>>>,
+/// Highest player count `create_session_json` fills up with random players.
+pub const MAX_AUTO_JOIN_PLAYERS: usize = 100;
+
+/// Dispatch `actions` as the joined player `player_idx`, refusing actions of any other sender.
+pub async fn dispatch_actions<G: TurboGame>(
+    session: Arc<Mutex<TurboSession<G>>>,
     actions: serde_json::Value,
     player_idx: usize,
-) -> Result<(), &'static str>
-where
-    PublicState: Serialize + Default + Send + Sync,
-    PrivateState: Default + Send + Sync,
-    GameAction: TurboActionSerialization + Send + Sync,
-{
-    let mut session_guard = session.lock().await;
+) -> Result<(), TurboError> {
+    dispatch_actions_as(&mut *session.lock().await, actions, Some(player_idx))
+}
+
+// `None` accepts any player sender, joining random players up to the highest index
+fn dispatch_actions_as<G: TurboGame>(
+    session_guard: &mut TurboSession<G>,
+    actions: serde_json::Value,
+    joined_player_idx: Option<usize>,
+) -> Result<(), TurboError> {
+    // Hex strings carry their own senders, objects are sent by the joined player
+    let player_idx = joined_player_idx.unwrap_or(0);
 
     let remaining_actions_vec = match actions {
         serde_json::Value::Array(_) => {
             let mut result: Vec<u8> = Vec::new();
             for action in actions.as_array().unwrap() {
-                let action_bytes = GameAction::serialize_json(&action.to_string())
-                    .map_err(|_| "Failed to serialize action")?;
+                let action_bytes = G::Action::serialize_json(&action.to_string())?;
                 if action.is_object() {
-                    let with_player = vec![player_idx as u8];
-                    result.extend(with_player);
+                    ActionSender::Player(player_idx).encode(&mut result)?;
                 }
                 result.extend(action_bytes);
             }
@@ -34,29 +50,67 @@ where
         }
         serde_json::Value::String(hex_str) => {
             // If it's a hex string, parse as Vec<u8>
-            hex::decode(hex_str.trim_start_matches("0x")).map_err(|_| "Failed to decode hex string")
+            hex::decode(hex_str.trim_start_matches("0x"))
+                .map_err(|_| TurboError::InvalidAction("Failed to decode hex string"))
         }
-        _ => return Err("Invalid input format"),
+        _ => return Err(TurboError::InvalidAction("Invalid input format")),
     }?;
 
     let mut remaining_actions = &remaining_actions_vec[..];
 
     while !remaining_actions.is_empty() {
-        let player_idx = remaining_actions[0] as usize;
+        let offset = remaining_actions_vec.len() - remaining_actions.len();
+        let (sender, action_bytes) =
+            ActionSender::decode(remaining_actions).map_err(|e| e.at_offset(offset))?;
 
-        if player_idx > 100 {
-            Err("Max 100 players")?;
-        }
+        let player_idx = match sender {
+            ActionSender::Player(player_idx) => player_idx,
+            // Only the host dispatches system actions, never clients
+            ActionSender::System(_) => {
+                return Err(TurboError::InvalidAction("System actions are reserved"))
+            }
+        };
 
-        while player_idx >= session_guard.player_count() {
-            session_guard.join_random();
+        match joined_player_idx {
+            Some(joined_player_idx) if player_idx != joined_player_idx => {
+                return Err(TurboError::InvalidAction(
+                    "Actions must be sent by the joined player",
+                ))
+            }
+            Some(_) => {}
+            None if player_idx >= MAX_AUTO_JOIN_PLAYERS => {
+                return Err(TurboError::UnknownPlayer(player_idx))
+            }
+            None => {
+                while player_idx >= session_guard.player_count() {
+                    session_guard.join_random()?;
+                }
+            }
         }
 
-        let (_action, next_actions) =
-            GameAction::deserialize(&remaining_actions[1..]).expect("Failed to deserialize action");
+        let action_offset = remaining_actions_vec.len() - action_bytes.len();
+        let (_action, mut next_actions) =
+            G::Action::deserialize(action_bytes).map_err(|e| e.at_offset(action_offset))?;
+
+        // Players with a key send a signature after each action
+        if session_guard.player_metadata()[player_idx]
+            .public_key
+            .is_some()
+        {
+            let signature_offset = remaining_actions_vec.len() - next_actions.len();
+            let (_signature, rest) =
+                split_signature(next_actions).map_err(|e| e.at_offset(signature_offset))?;
+            next_actions = rest;
+        }
 
         let action_bytes = &remaining_actions[0..remaining_actions.len() - next_actions.len()];
-        session_guard.dispatch(action_bytes)?;
+        // A passed deadline is applied before the late action
+        session_guard
+            .enforce_deadline()
+            .map_err(|e| e.at_offset(offset))?;
+        session_guard
+            .dispatch(action_bytes)
+            .map_err(|e| e.at_offset(offset))?;
 
         remaining_actions = next_actions;
     }
@@ -64,23 +118,66 @@ where
     Ok(())
 }
 
-pub async fn create_session_json<PublicState, PrivateState, GameAction>(
-    session_manager: &mut SessionManager<PublicState, PrivateState, GameAction>,
-    reducer: TurboReducer<PublicState, PrivateState, GameAction>,
-    actions: serde_json::Value,
-) -> Result<String, &'static str>
-where
-    PublicState: Serialize + Default + Send + Sync,
-    PrivateState: Default + Send + Sync,
-    GameAction: TurboActionSerialization + Send + Sync,
-{
-    let session_id = session_manager.create_session(reducer).await;
+/// Player seed and public key of a join request, a random seed if the client sends none.
+///
+/// A malformed seed or key is refused rather than dropped, a player that sent a key must never
+/// join without one.
+pub fn parse_player_metadata(request: &serde_json::Value) -> Result<PlayerMetadata, TurboError> {
+    let random_seed = match request.get("player_seed") {
+        Some(seed) => serde_json::from_value::<[u32; 16]>(seed.clone())
+            .map_err(|_| TurboError::InvalidAction("Invalid player seed"))?,
+        None => random_player_seed(),
+    };
+    let public_key = match request.get("public_key") {
+        Some(key) => Some(
+            serde_json::from_value::<PlayerPublicKey>(key.clone())
+                .map_err(|_| TurboError::InvalidAction("Invalid public key"))?,
+        ),
+        None => None,
+    };
+    Ok(PlayerMetadata {
+        random_seed,
+        public_key,
+    })
+}
+
+/// Parse a client supplied game configuration, `None` is the default configuration.
+pub fn parse_config<G: TurboGame>(
+    config: Option<&serde_json::Value>,
+) -> Result<G::Config, TurboError> {
+    match config {
+        Some(config) => serde_json::from_value(config.clone())
+            .map_err(|e| TurboError::InvalidConfig(e.to_string())),
+        None => Ok(G::Config::default()),
+    }
+}
+
+/// Create a session and dispatch the actions of `request` in it.
+///
+/// `request` is either the actions, as accepted by `dispatch_actions`, for a session with the
+/// default configuration, or an object with the game `config` and the `actions`. Players are
+/// joined with random seeds as the actions refer to them, up to `MAX_AUTO_JOIN_PLAYERS`.
+pub async fn create_session_json<G: TurboGame>(
+    session_manager: &mut SessionManager<G>,
+    request: serde_json::Value,
+) -> Result<String, TurboError> {
+    let (config, actions) = match request {
+        serde_json::Value::Object(mut request) => (
+            parse_config::<G>(request.get("config"))?,
+            request
+                .remove("actions")
+                .ok_or(TurboError::InvalidAction("Missing actions"))?,
+        ),
+        actions => (G::Config::default(), actions),
+    };
+
+    let session_id = session_manager.create_session(config).await;
     let session = session_manager
         .get_session(&session_id)
         .await
-        .ok_or("Failed to create session")?;
+        .ok_or(TurboError::SessionNotFound)?;
 
-    dispatch_actions(session, actions, 0).await?;
+    dispatch_actions_as(&mut *session.lock().await, actions, None)?;
 
     Ok(session_id)
 }
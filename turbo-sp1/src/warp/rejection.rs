@@ -1,5 +1,6 @@
 use serde_json::json;
 use std::convert::Infallible;
+use turbo_program::error::TurboError;
 use warp::http::StatusCode;
 use warp::reject::Rejection;
 use warp::reply::Reply;
@@ -32,6 +33,29 @@ impl ServerError {
         warp::reject::custom(Self::new(message, 404))
     }
 
+    /// Map a `TurboError` onto a status code: 4xx for bad client input, 5xx for server failures.
+    pub fn turbo_error(error: TurboError) -> Rejection {
+        let status_code = match error {
+            TurboError::Decode { .. }
+            | TurboError::InvalidAction(_)
+            | TurboError::InvalidConfig(_)
+            | TurboError::UnknownPlayer(_) => 400,
+            TurboError::InvalidSignature(_) => 401,
+            TurboError::SessionNotFound => 404,
+            TurboError::SessionBricked
+            | TurboError::SeedRevealed
+            | TurboError::SessionFinished
+            | TurboError::SessionNotFinished
+            | TurboError::PlayerLeft(_) => 409,
+            TurboError::ActionRejected(_) | TurboError::ReducerPanic(_) => 422,
+            TurboError::Setup(_)
+            | TurboError::Prover(_)
+            | TurboError::Execution(_)
+            | TurboError::Aggregation(_) => 500,
+        };
+        warp::reject::custom(Self::new(error.to_string(), status_code))
+    }
+
     pub fn message(&self) -> String {
         self.message.clone()
     }
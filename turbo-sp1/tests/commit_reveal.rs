@@ -0,0 +1,254 @@
+use alloy_sol_types::{sol, SolValue};
+use serde::{Deserialize, Serialize};
+use turbo_program::{
+    commit_reveal::{move_commitment, CommitReveal},
+    context::{TurboActionContext, TurboSystemContext},
+    crypto::fnv::FnvHasher,
+    error::{GameError, TurboError},
+    framing::{encode_actions, stamp_action, ActionSender, SYSTEM_DEADLINE},
+    game::{NoSystemAction, TurboGame},
+    metadata::PlayerMetadata,
+    program::run_turbo_program,
+    traits::{TurboAction, TurboActionSerialization},
+};
+use turbo_sp1::session::TurboSession;
+
+const REVEAL_WINDOW: u64 = 10_000;
+
+sol! {
+    #[derive(Serialize, Deserialize, Debug, Default)]
+    struct Scoreboard {
+        uint64 round;
+        uint32[] scores;
+    }
+}
+
+#[derive(TurboAction, Debug, PartialEq)]
+enum RpsAction {
+    Commit([u8; 32]),
+    // Salt and move: 0 rock, 1 paper, 2 scissors
+    Reveal([u8; 32], u8),
+}
+
+struct RockPaperScissors;
+
+impl RockPaperScissors {
+    fn next_round(public_state: &mut Scoreboard, round: &mut CommitReveal, winners: &[usize]) {
+        for &player in winners {
+            public_state.scores[player] += 1;
+        }
+        public_state.round += 1;
+        round.reset();
+    }
+}
+
+impl TurboGame for RockPaperScissors {
+    type PublicState = Scoreboard;
+    type PrivateState = CommitReveal;
+    type Output = Scoreboard;
+    type PlayerView = Scoreboard;
+    type Action = RpsAction;
+    type SystemAction = NoSystemAction;
+    type Config = ();
+    type ActionHasher = FnvHasher;
+
+    fn init(_config: &(), players: &[PlayerMetadata]) -> (Scoreboard, CommitReveal) {
+        let state = Scoreboard {
+            round: 0,
+            scores: vec![0; players.len()],
+        };
+        (state, CommitReveal::new(players.len()))
+    }
+
+    fn reduce(
+        public_state: &mut Scoreboard,
+        round: &mut CommitReveal,
+        action: &RpsAction,
+        context: &mut TurboActionContext,
+    ) -> Result<(), GameError> {
+        let player = context.player_index();
+        match action {
+            RpsAction::Commit(commitment) => {
+                round.commit(player, *commitment)?;
+                if round.all_committed() {
+                    round.set_deadline(context.timestamp() + REVEAL_WINDOW);
+                }
+            }
+            RpsAction::Reveal(salt, choice) => {
+                if *choice > 2 {
+                    return Err(GameError::new("Unknown move"));
+                }
+                round.reveal(player, salt, &[*choice], context.timestamp())?;
+                if round.all_revealed() {
+                    let (a, b) = (round.revealed(0).unwrap()[0], round.revealed(1).unwrap()[0]);
+                    let winners = match (3 + a - b) % 3 {
+                        0 => vec![],
+                        1 => vec![0],
+                        _ => vec![1],
+                    };
+                    Self::next_round(public_state, round, &winners);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn on_join(
+        public_state: &mut Scoreboard,
+        round: &mut CommitReveal,
+        _player_index: usize,
+        _context: &mut TurboSystemContext,
+    ) {
+        public_state.scores.push(0);
+        round.add_player();
+    }
+
+    fn deadline(_public_state: &Scoreboard, round: &CommitReveal) -> Option<u64> {
+        round.deadline()
+    }
+
+    // Players that revealed in time win the round
+    fn on_deadline(
+        public_state: &mut Scoreboard,
+        round: &mut CommitReveal,
+        _context: &mut TurboSystemContext,
+    ) {
+        let missing = round.missing_reveals();
+        let winners = (0..round.player_count())
+            .filter(|player| !missing.contains(player))
+            .collect::<Vec<_>>();
+        Self::next_round(public_state, round, &winners);
+    }
+}
+
+fn action(player: usize, action: RpsAction) -> Vec<u8> {
+    let mut action_raw = ActionSender::Player(player).to_bytes().unwrap();
+    action_raw.extend(action.serialize());
+    action_raw
+}
+
+fn commit(player: usize, salt: [u8; 32], choice: u8) -> Vec<u8> {
+    action(
+        player,
+        RpsAction::Commit(move_commitment(player, &salt, &[choice])),
+    )
+}
+
+fn rps_session() -> (TurboSession<RockPaperScissors>, u64) {
+    let mut session = TurboSession::<RockPaperScissors>::new(());
+    session.join_random().unwrap();
+    session.join_random().unwrap();
+    let start = session.timestamp();
+    (session, start)
+}
+
+// Commits of both players and a reveal of player 0, before the deadline
+fn missed_reveal_session() -> (TurboSession<RockPaperScissors>, u64) {
+    let (mut session, start) = rps_session();
+    session.dispatch_at(&commit(0, [1; 32], 1), start).unwrap();
+    session.dispatch_at(&commit(1, [2; 32], 2), start).unwrap();
+    session
+        .dispatch_at(&action(0, RpsAction::Reveal([1; 32], 1)), start + 1)
+        .unwrap();
+    (session, start + REVEAL_WINDOW)
+}
+
+#[test]
+fn moves_are_revealed_after_every_commit() {
+    let (mut session, start) = rps_session();
+
+    session.dispatch_at(&commit(0, [1; 32], 0), start).unwrap();
+    assert_eq!(
+        session.dispatch_at(&action(0, RpsAction::Reveal([1; 32], 0)), start + 1),
+        Err(TurboError::ActionRejected(
+            "Waiting for every player to commit".to_string()
+        ))
+    );
+    session
+        .dispatch_at(&commit(1, [2; 32], 2), start + 2)
+        .unwrap();
+    assert_eq!(
+        session.private_state().deadline(),
+        Some(start + 2 + REVEAL_WINDOW)
+    );
+
+    // A reveal that does not match the commitment is refused
+    assert_eq!(
+        session.dispatch_at(&action(1, RpsAction::Reveal([2; 32], 1)), start + 3),
+        Err(TurboError::ActionRejected(
+            "Reveal does not match the commitment".to_string()
+        ))
+    );
+
+    session
+        .dispatch_at(&action(0, RpsAction::Reveal([1; 32], 0)), start + 4)
+        .unwrap();
+    session
+        .dispatch_at(&action(1, RpsAction::Reveal([2; 32], 2)), start + 5)
+        .unwrap();
+
+    // Rock beats scissors
+    assert_eq!(session.public_state().round, 1);
+    assert_eq!(session.public_state().scores, vec![1, 0]);
+    assert_eq!(session.private_state().deadline(), None);
+}
+
+#[test]
+fn missed_reveals_are_penalised_at_the_deadline() {
+    let (mut session, deadline) = missed_reveal_session();
+    let deadline_action = ActionSender::System(SYSTEM_DEADLINE).to_bytes().unwrap();
+    assert_eq!(
+        session.dispatch_at(&deadline_action, deadline - 1),
+        Err(TurboError::InvalidAction("Deadline not reached"))
+    );
+    assert_eq!(session.enforce_deadline_at(deadline - 1), Ok(false));
+
+    // Player 1 loses the round by not revealing
+    assert_eq!(session.enforce_deadline_at(deadline), Ok(true));
+    assert_eq!(session.public_state().round, 1);
+    assert_eq!(session.public_state().scores, vec![1, 0]);
+
+    assert_eq!(
+        session.dispatch_at(&deadline_action, deadline + 1),
+        Err(TurboError::InvalidAction("No deadline pending"))
+    );
+    assert_eq!(session.enforce_deadline_at(deadline + 1), Ok(false));
+}
+
+fn run_program(session: &TurboSession<RockPaperScissors>, actions: &[u8]) -> Scoreboard {
+    let public_values = run_turbo_program::<RockPaperScissors>(
+        session.server_metadata(),
+        &(),
+        session.initial_player_metadata().to_vec(),
+        None,
+        &encode_actions(actions),
+        session.view_secret(),
+    );
+    assert_eq!(public_values.actionHashes, session.action_hashes());
+    assert_eq!(public_values.systemActionHash, session.system_action_hash());
+    assert_eq!(public_values.stateHash.0, session.state_hash());
+    assert_eq!(public_values.state.to_vec(), session.output().abi_encode());
+    Scoreboard::abi_decode(&public_values.state).unwrap()
+}
+
+#[test]
+fn program_applies_the_deadline_like_the_session() {
+    let (mut session, deadline) = missed_reveal_session();
+    assert_eq!(session.enforce_deadline_at(deadline), Ok(true));
+
+    let scoreboard = run_program(&session, session.actions());
+    assert_eq!(scoreboard.round, 1);
+    assert_eq!(scoreboard.scores, vec![1, 0]);
+}
+
+#[test]
+#[should_panic(expected = "Deadline not reached")]
+fn program_refuses_an_early_deadline() {
+    let (session, deadline) = missed_reveal_session();
+    let early = stamp_action(
+        &ActionSender::System(SYSTEM_DEADLINE).to_bytes().unwrap(),
+        deadline - 1,
+    )
+    .unwrap();
+    run_program(&session, &[session.actions().clone(), early].concat());
+}
@@ -0,0 +1,166 @@
+use game_lib::game::Game2048;
+use turbo_sp1::{
+    error::TurboError,
+    framing::{stamp_action, ActionSender},
+    metadata::PlayerMetadata,
+    session::{random_player_seed, TurboSession},
+};
+
+#[test]
+fn rejected_action_is_skipped() {
+    let mut session = TurboSession::<Game2048>::new(());
+    let player_idx = session.join_random().unwrap();
+
+    session.dispatch(&[player_idx as u8, 2]).unwrap();
+    let actions = session.actions().clone();
+    let board = session.public_state().board;
+
+    let result = session.dispatch(&[player_idx as u8, 7]);
+    assert!(matches!(result, Err(TurboError::ActionRejected(_))));
+    assert!(!session.is_bricked());
+    assert_eq!(session.actions(), &actions);
+    assert_eq!(session.public_state().board, board);
+
+    session.dispatch(&[player_idx as u8, 3]).unwrap();
+}
+
+#[test]
+fn unknown_player_is_an_error() {
+    let mut session = TurboSession::<Game2048>::new(());
+    session.join_random().unwrap();
+
+    assert_eq!(session.dispatch(&[1, 2]), Err(TurboError::UnknownPlayer(1)));
+    assert!(matches!(
+        session.dispatch(&[0]),
+        Err(TurboError::Decode { offset: 1, .. })
+    ));
+}
+
+#[test]
+fn checkpoint_starts_a_new_segment() {
+    let mut session = TurboSession::<Game2048>::new(());
+    let player_idx = session.join_random().unwrap();
+
+    session.dispatch(&[player_idx as u8, 2]).unwrap();
+    assert_eq!(session.prior_state_hash(), [0u8; 32]);
+
+    let state_hash = session.checkpoint();
+    assert_eq!(state_hash, session.state_hash());
+    assert_eq!(session.prior_state_hash(), state_hash);
+    assert!(session.pending_actions().is_empty());
+
+    session
+        .dispatch_at(&[player_idx as u8, 3], u64::MAX)
+        .unwrap();
+    let action = stamp_action(&[player_idx as u8, 3], u64::MAX).unwrap();
+    assert_eq!(session.pending_actions(), &action);
+    assert!(session.actions().ends_with(&action));
+    assert_ne!(session.state_hash(), state_hash);
+}
+
+#[test]
+fn every_checkpoint_closes_a_segment() {
+    let mut session = TurboSession::<Game2048>::new(());
+    let player_idx = session.join_random().unwrap();
+    assert_eq!(session.segment_stdins().len(), 1);
+
+    session.dispatch(&[player_idx as u8, 2]).unwrap();
+    session.checkpoint();
+    assert_eq!(session.segment_stdins().len(), 1);
+
+    session.dispatch(&[player_idx as u8, 3]).unwrap();
+    assert_eq!(session.segment_stdins().len(), 2);
+    session.checkpoint();
+    assert_eq!(session.segment_stdins().len(), 2);
+}
+
+#[test]
+fn revealing_the_seed_closes_the_session() {
+    let mut session = TurboSession::<Game2048>::new(());
+    let player_idx = session.join_random().unwrap();
+    let commitment = session.seed_commitment();
+
+    session.dispatch(&[player_idx as u8, 2]).unwrap();
+    let reveal = session.reveal_server_seed();
+    assert!(reveal.verify());
+    assert_eq!(reveal.commitment, commitment);
+
+    assert_eq!(
+        session.dispatch(&[player_idx as u8, 3]),
+        Err(TurboError::SeedRevealed)
+    );
+}
+
+#[test]
+fn players_beyond_one_byte_can_dispatch() {
+    let mut session = TurboSession::<Game2048>::new(());
+    for _ in 0..300 {
+        session.join_random().unwrap();
+    }
+
+    let mut action = ActionSender::Player(299).to_bytes().unwrap();
+    action.push(2);
+    session.dispatch_at(&action, 1).unwrap();
+    assert_eq!(session.actions(), &stamp_action(&action, 1).unwrap());
+
+    assert!(matches!(
+        session.dispatch(&[0x70, 2]),
+        Err(TurboError::Decode { offset: 1, .. })
+    ));
+}
+
+#[test]
+fn timestamps_never_decrease() {
+    let mut session = TurboSession::<Game2048>::new(());
+    let player_idx = session.join_random().unwrap() as u8;
+
+    session.dispatch_at(&[player_idx, 2], 1_000).unwrap();
+    assert_eq!(session.timestamp(), 1_000);
+    assert_eq!(
+        session.dispatch_at(&[player_idx, 3], 999),
+        Err(TurboError::InvalidAction(
+            "Timestamp before the previous action"
+        ))
+    );
+
+    // A rejected action does not move the clock
+    assert!(session.dispatch_at(&[player_idx, 7], 5_000).is_err());
+    assert_eq!(session.timestamp(), 1_000);
+
+    session.dispatch(&[player_idx, 3]).unwrap();
+    assert!(session.timestamp() > 1_000);
+}
+
+#[test]
+fn action_hashes_cover_the_timestamp() {
+    let mut early = TurboSession::<Game2048>::new(());
+    let mut late =
+        TurboSession::<Game2048>::with_server_metadata((), early.server_metadata().clone());
+    let player = PlayerMetadata::new(random_player_seed());
+    early.join(player.clone()).unwrap();
+    late.join(player).unwrap();
+
+    early.dispatch_at(&[0, 2], 1_000).unwrap();
+    late.dispatch_at(&[0, 2], 2_000).unwrap();
+    assert_eq!(early.public_state().board, late.public_state().board);
+    assert_ne!(early.action_hashes(), late.action_hashes());
+}
+
+#[test]
+fn private_state_is_hidden_until_the_game_is_over() {
+    let mut session = TurboSession::<Game2048>::new(());
+    let player_idx = session.join_random().unwrap();
+    session.dispatch(&[player_idx as u8, 2]).unwrap();
+
+    assert_eq!(
+        session.reveal_private_state(),
+        Err(TurboError::SessionNotFinished)
+    );
+
+    // Closing the session early does not reveal the private state of a game still going on
+    session.reveal_server_seed();
+    assert_eq!(
+        session.reveal_private_state(),
+        Err(TurboError::SessionNotFinished)
+    );
+}
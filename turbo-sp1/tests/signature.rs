@@ -0,0 +1,218 @@
+use ed25519_dalek::{Signer as _, SigningKey};
+use game_lib::game::Game2048;
+use serde_json::json;
+use turbo_sp1::{
+    crypto::signature::{action_signing_message, PlayerPublicKey},
+    error::TurboError,
+    framing::{encode_actions, stamp_action, unstamp_action, ActionSender},
+    metadata::PlayerMetadata,
+    program::run_turbo_program,
+    session::{random_player_seed, TurboSession},
+    session_simple::parse_player_metadata,
+};
+
+fn signed_action(
+    session: &TurboSession<Game2048>,
+    key: &SigningKey,
+    sequence: u64,
+    action: u8,
+) -> Vec<u8> {
+    let message = action_signing_message(&session.seed_commitment(), sequence, &[action]);
+    let mut action_raw = vec![0, action];
+    action_raw.extend(key.sign(&message).to_bytes());
+    action_raw
+}
+
+fn ed25519_session(key: &SigningKey) -> TurboSession<Game2048> {
+    let mut session = TurboSession::<Game2048>::new(());
+    session
+        .join(PlayerMetadata {
+            random_seed: random_player_seed(),
+            public_key: Some(PlayerPublicKey::Ed25519(key.verifying_key().to_bytes())),
+        })
+        .unwrap();
+    session
+}
+
+#[test]
+fn signed_actions_are_verified() {
+    let key = SigningKey::from_bytes(&[7; 32]);
+    let mut session = ed25519_session(&key);
+
+    let first = signed_action(&session, &key, 0, 2);
+    session.dispatch(&first).unwrap();
+    session
+        .dispatch(&signed_action(&session, &key, 1, 3))
+        .unwrap();
+
+    // The signature is kept in the action stream for the zkVM to verify again
+    let (_, frame) = unstamp_action(session.actions()).unwrap();
+    assert!(frame.starts_with(&first));
+}
+
+#[test]
+fn unsigned_and_forged_actions_are_refused() {
+    let key = SigningKey::from_bytes(&[7; 32]);
+    let mut session = ed25519_session(&key);
+
+    assert!(matches!(
+        session.dispatch(&[0, 2]),
+        Err(TurboError::Decode { offset: 2, .. })
+    ));
+
+    let other = SigningKey::from_bytes(&[8; 32]);
+    assert_eq!(
+        session.dispatch(&signed_action(&session, &other, 0, 2)),
+        Err(TurboError::InvalidSignature(0))
+    );
+    assert!(session.actions().is_empty());
+}
+
+#[test]
+fn signatures_cannot_be_replayed() {
+    let key = SigningKey::from_bytes(&[7; 32]);
+    let mut session = ed25519_session(&key);
+
+    let action = signed_action(&session, &key, 0, 2);
+    session.dispatch(&action).unwrap();
+    assert_eq!(
+        session.dispatch(&action),
+        Err(TurboError::InvalidSignature(0))
+    );
+
+    // Nor moved to another session
+    let mut other = ed25519_session(&key);
+    assert_eq!(
+        other.dispatch(&action),
+        Err(TurboError::InvalidSignature(0))
+    );
+}
+
+#[test]
+fn rejected_actions_do_not_use_up_a_sequence_number() {
+    let key = SigningKey::from_bytes(&[7; 32]);
+    let mut session = ed25519_session(&key);
+
+    let first = signed_action(&session, &key, 0, 2);
+    session.dispatch_at(&first, 1_000).unwrap();
+    let rejected = signed_action(&session, &key, 1, 9);
+    assert!(matches!(
+        session.dispatch_at(&rejected, 2_000),
+        Err(TurboError::ActionRejected(_))
+    ));
+    let next = signed_action(&session, &key, 1, 3);
+    session.dispatch_at(&next, 3_000).unwrap();
+
+    // The program skips the rejected action the same way when it is kept in the transcript
+    let actions = [(1_000, &first), (2_000, &rejected), (3_000, &next)]
+        .into_iter()
+        .flat_map(|(timestamp, action)| stamp_action(action, timestamp).unwrap())
+        .collect::<Vec<_>>();
+    let public_values = run_turbo_program::<Game2048>(
+        session.server_metadata(),
+        &(),
+        session.initial_player_metadata().to_vec(),
+        None,
+        &encode_actions(&actions),
+        session.view_secret(),
+    );
+    assert_eq!(public_values.actionHashes, session.action_hashes());
+    assert_eq!(public_values.stateHash.0, session.state_hash());
+}
+
+#[test]
+fn secp256k1_keys_sign_actions() {
+    let key = k256::ecdsa::SigningKey::from_slice(&[7; 32]).unwrap();
+    let public_key = key
+        .verifying_key()
+        .to_encoded_point(true)
+        .as_bytes()
+        .to_vec();
+
+    let mut session = TurboSession::<Game2048>::new(());
+    session.join_random().unwrap();
+    session.dispatch(&[0, 2]).unwrap();
+
+    // A player joining later brings the key in the join action
+    let player_idx = session
+        .join(PlayerMetadata {
+            random_seed: random_player_seed(),
+            public_key: Some(PlayerPublicKey::Secp256k1(public_key)),
+        })
+        .unwrap();
+    assert!(session.player_metadata()[player_idx].public_key.is_some());
+
+    let message = action_signing_message(&session.seed_commitment(), 0, &[3]);
+    let signature: k256::ecdsa::Signature = k256::ecdsa::signature::Signer::sign(&key, &message);
+    let mut action_raw = ActionSender::Player(player_idx).to_bytes().unwrap();
+    action_raw.push(3);
+    action_raw.extend(signature.to_bytes());
+    session.dispatch(&action_raw).unwrap();
+
+    // Other players still act without signatures
+    session.dispatch(&[0, 2]).unwrap();
+}
+
+#[test]
+fn unusable_keys_are_refused_at_join() {
+    let key = k256::ecdsa::SigningKey::from_slice(&[7; 32]).unwrap();
+    let uncompressed = key
+        .verifying_key()
+        .to_encoded_point(false)
+        .as_bytes()
+        .to_vec();
+    let keys = [
+        PlayerPublicKey::Secp256k1(uncompressed),
+        // x above the field prime
+        PlayerPublicKey::Secp256k1([vec![2], vec![0xff; 32]].concat()),
+        PlayerPublicKey::Secp256k1(vec![2; 5]),
+        PlayerPublicKey::Ed25519([2; 32]),
+    ];
+
+    let mut session = TurboSession::<Game2048>::new(());
+    for public_key in &keys {
+        let player_metadata = PlayerMetadata {
+            random_seed: random_player_seed(),
+            public_key: Some(public_key.clone()),
+        };
+        assert_eq!(
+            session.join(player_metadata),
+            Err(TurboError::InvalidAction("Invalid public key"))
+        );
+    }
+
+    // Joins after the first action are refused the same way
+    session.join_random().unwrap();
+    session.dispatch(&[0, 2]).unwrap();
+    for public_key in keys {
+        let player_metadata = PlayerMetadata {
+            random_seed: random_player_seed(),
+            public_key: Some(public_key),
+        };
+        assert_eq!(
+            session.join(player_metadata),
+            Err(TurboError::InvalidAction("Invalid public key"))
+        );
+    }
+    assert_eq!(session.player_count(), 1);
+}
+
+#[test]
+fn malformed_join_requests_are_refused() {
+    let key = SigningKey::from_bytes(&[7; 32]);
+    let public_key = PlayerPublicKey::Ed25519(key.verifying_key().to_bytes());
+
+    let player_metadata = parse_player_metadata(&json!({ "public_key": public_key })).unwrap();
+    assert_eq!(player_metadata.public_key, Some(public_key));
+    assert_eq!(parse_player_metadata(&json!({})).unwrap().public_key, None);
+
+    // A player that sent a key or a seed never joins without it
+    assert_eq!(
+        parse_player_metadata(&json!({ "public_key": { "Ed25519": [1, 2, 3] } })).unwrap_err(),
+        TurboError::InvalidAction("Invalid public key")
+    );
+    assert_eq!(
+        parse_player_metadata(&json!({ "player_seed": "0x1234" })).unwrap_err(),
+        TurboError::InvalidAction("Invalid player seed")
+    );
+}
@@ -0,0 +1,550 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use alloy_sol_types::{sol, SolValue};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use turbo_program::{
+    context::{TurboActionContext, TurboSystemContext},
+    crypto::fnv::FnvHasher,
+    error::{GameError, TurboError},
+    framing::{
+        encode_actions, stamp_action, unstamp_action, ActionSender, SYSTEM_GAME_ACTION,
+        SYSTEM_JOIN, SYSTEM_LEAVE,
+    },
+    game::{TurboGame, TurboOutput},
+    metadata::PlayerMetadata,
+    program::trace_turbo_program,
+    traits::{TurboAction, TurboActionSerialization},
+};
+use turbo_sp1::{
+    session::TurboSession,
+    session_manager::SessionManager,
+    session_simple::{create_session_json, dispatch_actions},
+};
+
+sol! {
+    #[derive(Serialize, Deserialize, Debug, Default)]
+    struct RoundState {
+        uint64 round;
+        uint32 roll;
+        uint32 moves;
+        uint32 present;
+        uint64 lastMoveAt;
+        uint64 maxRounds;
+    }
+
+    /// Committed instead of the whole state
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct RoundSummary {
+        uint64 round;
+        uint32 present;
+    }
+}
+
+impl TurboOutput<RoundState, ()> for RoundSummary {
+    fn from_state(public_state: &RoundState, _private_state: &()) -> Self {
+        RoundSummary {
+            round: public_state.round,
+            present: public_state.present,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct RoundConfig {
+    dice_sides: u32,
+    max_rounds: Option<u64>,
+}
+
+impl Default for RoundConfig {
+    fn default() -> Self {
+        RoundConfig {
+            dice_sides: 6,
+            max_rounds: None,
+        }
+    }
+}
+
+#[derive(TurboAction, Debug, PartialEq)]
+enum RoundAction {
+    Move,
+    Stall,
+}
+
+#[derive(TurboAction, Debug, PartialEq)]
+enum RoundSystemAction {
+    EndRound,
+    Reject,
+}
+
+struct RoundGame;
+
+impl TurboGame for RoundGame {
+    type PublicState = RoundState;
+    type PrivateState = ();
+    type Output = RoundSummary;
+    type PlayerView = RoundState;
+    type Action = RoundAction;
+    type SystemAction = RoundSystemAction;
+    type Config = RoundConfig;
+    type ActionHasher = FnvHasher;
+
+    fn init(config: &RoundConfig, players: &[PlayerMetadata]) -> (RoundState, ()) {
+        let state = RoundState {
+            present: players.len() as u32,
+            maxRounds: config.max_rounds.unwrap_or(u64::MAX),
+            ..Default::default()
+        };
+        (state, ())
+    }
+
+    fn is_terminal(public_state: &RoundState, _private_state: &()) -> bool {
+        public_state.round >= public_state.maxRounds
+    }
+
+    fn reduce(
+        public_state: &mut RoundState,
+        _private_state: &mut (),
+        action: &RoundAction,
+        context: &mut TurboActionContext<FnvHasher, RoundConfig>,
+    ) -> Result<(), GameError> {
+        public_state.moves += 1;
+        if *action == RoundAction::Stall {
+            return Err(GameError::new("Stalling"));
+        }
+        public_state.lastMoveAt = context.timestamp();
+        Ok(())
+    }
+
+    fn reduce_system(
+        public_state: &mut RoundState,
+        _private_state: &mut (),
+        action: &RoundSystemAction,
+        context: &mut TurboSystemContext<FnvHasher, RoundConfig>,
+    ) -> Result<(), GameError> {
+        match action {
+            RoundSystemAction::EndRound => {
+                public_state.round = context.tick() + 1;
+                let dice_sides = context.config().dice_sides;
+                public_state.roll = context.rand_range(0..dice_sides);
+                Ok(())
+            }
+            RoundSystemAction::Reject => {
+                // Changes made before rejecting are discarded
+                public_state.roll = context.rand_u32();
+                Err(GameError::new("Rejected"))
+            }
+        }
+    }
+
+    fn on_join(
+        public_state: &mut RoundState,
+        _private_state: &mut (),
+        player_index: usize,
+        context: &mut TurboSystemContext<FnvHasher, RoundConfig>,
+    ) {
+        assert_eq!(player_index + 1, context.player_count());
+        public_state.present += 1;
+    }
+
+    fn on_leave(
+        public_state: &mut RoundState,
+        _private_state: &mut (),
+        _player_index: usize,
+        _context: &mut TurboSystemContext<FnvHasher, RoundConfig>,
+    ) {
+        public_state.present -= 1;
+    }
+}
+
+#[test]
+fn system_actions_reach_the_system_reducer() {
+    let mut session = TurboSession::<RoundGame>::new(RoundConfig::default());
+    let player_idx = session.join_random().unwrap();
+
+    session.dispatch(&[player_idx as u8, 0]).unwrap();
+    session
+        .dispatch_system(&RoundSystemAction::EndRound)
+        .unwrap();
+    session
+        .dispatch_system(&RoundSystemAction::EndRound)
+        .unwrap();
+
+    assert_eq!(session.tick(), 2);
+    assert_eq!(session.public_state().round, 2);
+    assert_eq!(session.public_state().moves, 1);
+
+    // Every action is stamped: sender, 8 timestamp bytes and one action byte
+    assert_eq!(session.actions().len(), 3 * 10);
+    assert_eq!(session.actions()[10], 0x70);
+}
+
+#[test]
+fn output_commits_the_summary() {
+    let mut session = TurboSession::<RoundGame>::new(RoundConfig::default());
+    let player_idx = session.join_random().unwrap();
+    session.dispatch(&[player_idx as u8, 0]).unwrap();
+    session
+        .dispatch_system(&RoundSystemAction::EndRound)
+        .unwrap();
+
+    let output = session.output();
+    assert_eq!(
+        output,
+        RoundSummary {
+            round: 1,
+            present: 1
+        }
+    );
+    assert_eq!(
+        RoundSummary::abi_decode(&output.abi_encode()).unwrap(),
+        output
+    );
+    assert_eq!(session.public_state().moves, 1);
+}
+
+#[test]
+fn rejected_action_leaves_the_state_untouched() {
+    let mut session = TurboSession::<RoundGame>::new(RoundConfig::default());
+    let player_idx = session.join_random().unwrap();
+    session.dispatch(&[player_idx as u8, 0]).unwrap();
+
+    assert_eq!(
+        session.dispatch(&[player_idx as u8, 1]),
+        Err(TurboError::ActionRejected("Stalling".to_string()))
+    );
+    assert_eq!(session.public_state().moves, 1);
+}
+
+#[test]
+fn rejected_system_action_leaves_the_context_untouched() {
+    let mut session = TurboSession::<RoundGame>::new(RoundConfig::default());
+    let hash = session.system_action_hash();
+
+    assert!(matches!(
+        session.dispatch_system(&RoundSystemAction::Reject),
+        Err(TurboError::ActionRejected(_))
+    ));
+    assert_eq!(session.tick(), 0);
+    assert_eq!(session.system_action_hash(), hash);
+    assert_eq!(session.public_state().roll, 0);
+    assert!(session.actions().is_empty());
+
+    session
+        .dispatch_system(&RoundSystemAction::EndRound)
+        .unwrap();
+    assert_ne!(session.system_action_hash(), hash);
+}
+
+#[test]
+fn system_rng_depends_on_the_server_seed_only() {
+    let mut a = TurboSession::<RoundGame>::new(RoundConfig::default());
+    let mut b = TurboSession::<RoundGame>::with_server_metadata(
+        RoundConfig::default(),
+        a.server_metadata().clone(),
+    );
+    a.join_random().unwrap();
+
+    let mut end_round = ActionSender::System(SYSTEM_GAME_ACTION).to_bytes().unwrap();
+    end_round.extend(RoundSystemAction::EndRound.serialize());
+    for timestamp in 0..8 {
+        a.dispatch_at(&end_round, timestamp).unwrap();
+        b.dispatch_at(&end_round, timestamp).unwrap();
+        assert_eq!(a.public_state().roll, b.public_state().roll);
+    }
+    assert_eq!(a.system_action_hash(), b.system_action_hash());
+}
+
+#[test]
+fn config_is_read_by_the_reducer_and_committed() {
+    let mut normal = TurboSession::<RoundGame>::new(RoundConfig::default());
+    let mut hard = TurboSession::<RoundGame>::with_server_metadata(
+        RoundConfig {
+            dice_sides: 1,
+            ..Default::default()
+        },
+        normal.server_metadata().clone(),
+    );
+
+    let mut end_round = ActionSender::System(SYSTEM_GAME_ACTION).to_bytes().unwrap();
+    end_round.extend(RoundSystemAction::EndRound.serialize());
+    for timestamp in 0..8 {
+        normal.dispatch_at(&end_round, timestamp).unwrap();
+        hard.dispatch_at(&end_round, timestamp).unwrap();
+        assert!(normal.public_state().roll < 6);
+        assert_eq!(hard.public_state().roll, 0);
+    }
+    assert_ne!(normal.config_hash(), hard.config_hash());
+}
+
+#[tokio::test]
+async fn sessions_created_over_http_use_the_requested_config() {
+    let mut session_manager = SessionManager::<RoundGame>::new();
+
+    let request = json!({ "config": { "dice_sides": 1, "max_rounds": 3 }, "actions": [] });
+    let session_id = create_session_json(&mut session_manager, request)
+        .await
+        .unwrap();
+    let session = session_manager.get_session(&session_id).await.unwrap();
+    assert_eq!(session.lock().await.config().dice_sides, 1);
+    assert_eq!(session.lock().await.public_state().maxRounds, 3);
+
+    // Bare actions keep the default config
+    let session_id = create_session_json(&mut session_manager, json!([]))
+        .await
+        .unwrap();
+    let session = session_manager.get_session(&session_id).await.unwrap();
+    assert_eq!(session.lock().await.config().dice_sides, 6);
+
+    let request = json!({ "config": { "dice_sides": "six" }, "actions": [] });
+    assert!(matches!(
+        create_session_json(&mut session_manager, request).await,
+        Err(TurboError::InvalidConfig(_))
+    ));
+}
+
+#[tokio::test]
+async fn clients_only_act_as_the_player_they_joined_as() {
+    let mut session_manager = SessionManager::<RoundGame>::new();
+    let session_id = session_manager.create_session(RoundConfig::default()).await;
+    let session = session_manager.get_session(&session_id).await.unwrap();
+    session.lock().await.join_random().unwrap();
+    session.lock().await.join_random().unwrap();
+
+    // Hex actions name their sender: another player, or one nobody joined as
+    for actions in ["0x0100", "0xff0700"] {
+        assert!(matches!(
+            dispatch_actions(session.clone(), json!(actions), 0).await,
+            Err(TurboError::InvalidAction(_))
+        ));
+    }
+    assert_eq!(session.lock().await.player_count(), 2);
+    assert_eq!(session.lock().await.public_state().moves, 0);
+
+    dispatch_actions(session.clone(), json!("0x0000"), 0)
+        .await
+        .unwrap();
+    assert_eq!(session.lock().await.public_state().moves, 1);
+
+    // Only sessions created over HTTP join players as the actions refer to them
+    let session_id = create_session_json(&mut session_manager, json!("0x0200"))
+        .await
+        .unwrap();
+    let session = session_manager.get_session(&session_id).await.unwrap();
+    assert_eq!(session.lock().await.player_count(), 3);
+    assert!(matches!(
+        create_session_json(&mut session_manager, json!("0x6400")).await,
+        Err(TurboError::UnknownPlayer(100))
+    ));
+}
+
+#[test]
+fn finish_hooks_run_once_whichever_action_ends_the_game() {
+    let mut session = TurboSession::<RoundGame>::new(RoundConfig {
+        max_rounds: Some(2),
+        ..Default::default()
+    });
+    session.join_random().unwrap();
+    session.join_random().unwrap();
+
+    let finished = Arc::new(AtomicUsize::new(0));
+    let counter = finished.clone();
+    session.on_finish(move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+
+    // Player 1 moves and the server ends the game, the hook does not depend on player 0
+    session.dispatch(&[1, 0]).unwrap();
+    session
+        .dispatch_system(&RoundSystemAction::EndRound)
+        .unwrap();
+    assert_eq!(finished.load(Ordering::SeqCst), 0);
+    session
+        .dispatch_system(&RoundSystemAction::EndRound)
+        .unwrap();
+    assert_eq!(finished.load(Ordering::SeqCst), 1);
+
+    // Registered after the end, it runs right away
+    let counter = finished.clone();
+    session.on_finish(move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    assert_eq!(finished.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn terminal_state_finishes_the_session() {
+    let mut session = TurboSession::<RoundGame>::new(RoundConfig {
+        max_rounds: Some(2),
+        ..Default::default()
+    });
+    let player_idx = session.join_random().unwrap();
+
+    session
+        .dispatch_system(&RoundSystemAction::EndRound)
+        .unwrap();
+    assert!(!session.is_finished());
+    assert_eq!(
+        session.reveal_private_state(),
+        Err(TurboError::SessionNotFinished)
+    );
+    session
+        .dispatch_system(&RoundSystemAction::EndRound)
+        .unwrap();
+    assert!(session.is_finished());
+
+    // The private state of a finished game can be revealed
+    let reveal = session.reveal_private_state().unwrap();
+    assert!(reveal.verify());
+    assert_eq!(reveal.commitment, session.private_state_commitment());
+    assert_eq!(reveal.decode::<()>(), Some(()));
+
+    let actions = session.actions().clone();
+    assert_eq!(
+        session.dispatch(&[player_idx as u8, 0]),
+        Err(TurboError::SessionFinished)
+    );
+    assert_eq!(
+        session.dispatch_system(&RoundSystemAction::EndRound),
+        Err(TurboError::SessionFinished)
+    );
+    assert_eq!(session.join_random(), Err(TurboError::SessionFinished));
+    assert_eq!(session.actions(), &actions);
+    assert_eq!(
+        session.serialize_json(player_idx).unwrap()["finished"],
+        true
+    );
+}
+
+#[test]
+fn unknown_system_kinds_are_rejected() {
+    let mut session = TurboSession::<RoundGame>::new(RoundConfig::default());
+    assert_eq!(
+        session.dispatch(&ActionSender::System(0xf).to_bytes().unwrap()),
+        Err(TurboError::InvalidAction("Unknown system action"))
+    );
+}
+
+#[test]
+fn players_joining_before_the_first_action_are_initial() {
+    let mut session = TurboSession::<RoundGame>::new(RoundConfig::default());
+    session.join_random().unwrap();
+    session.join_random().unwrap();
+
+    assert_eq!(session.public_state().present, 2);
+    assert!(session.actions().is_empty());
+}
+
+#[test]
+fn joins_and_leaves_are_recorded_in_the_action_stream() {
+    let mut session = TurboSession::<RoundGame>::new(RoundConfig::default());
+    session.join_random().unwrap();
+
+    let mut join = ActionSender::System(SYSTEM_JOIN).to_bytes().unwrap();
+    join.extend([0, 0, 0, 9].repeat(16));
+    // No public key
+    join.push(0);
+    let mut leave = ActionSender::System(SYSTEM_LEAVE).to_bytes().unwrap();
+    leave.extend([0, 0, 0, 0]);
+
+    session.dispatch_at(&[0, 0], 10).unwrap();
+    session.dispatch_at(&join, 20).unwrap();
+    assert_eq!(session.player_metadata()[1].random_seed, [9; 16]);
+    assert_eq!(session.public_state().present, 2);
+    session.dispatch_at(&[1, 0], 30).unwrap();
+    assert_eq!(session.public_state().lastMoveAt, 30);
+
+    session.dispatch_at(&leave, 40).unwrap();
+    assert_eq!(session.public_state().present, 1);
+    assert!(!session.is_active(0));
+    assert!(session.is_active(1));
+    assert_eq!(session.dispatch(&[0, 0]), Err(TurboError::PlayerLeft(0)));
+    assert_eq!(session.leave(0), Err(TurboError::PlayerLeft(0)));
+    assert_eq!(session.leave(2), Err(TurboError::UnknownPlayer(2)));
+    assert_eq!(
+        session.leave(usize::MAX),
+        Err(TurboError::UnknownPlayer(usize::MAX))
+    );
+
+    let actions = [
+        stamp_action(&[0, 0], 10).unwrap(),
+        stamp_action(&join, 20).unwrap(),
+        stamp_action(&[1, 0], 30).unwrap(),
+        stamp_action(&leave, 40).unwrap(),
+    ]
+    .concat();
+    assert_eq!(session.actions(), &actions);
+}
+
+#[test]
+fn replaying_the_action_stream_restores_the_players() {
+    let mut session = TurboSession::<RoundGame>::new(RoundConfig::default());
+    session.join_random().unwrap();
+
+    // Split the action stream into frames as they are dispatched
+    let mut frames = Vec::new();
+    let mut record = |session: &TurboSession<RoundGame>, start: &mut usize| {
+        frames.push(session.actions()[*start..].to_vec());
+        *start = session.actions().len();
+    };
+    let mut start = 0;
+    session.dispatch(&[0, 0]).unwrap();
+    record(&session, &mut start);
+    session.join_random().unwrap();
+    record(&session, &mut start);
+    session.leave(0).unwrap();
+    record(&session, &mut start);
+    session
+        .dispatch_system(&RoundSystemAction::EndRound)
+        .unwrap();
+    record(&session, &mut start);
+
+    let mut replay = TurboSession::<RoundGame>::with_server_metadata(
+        RoundConfig::default(),
+        session.server_metadata().clone(),
+    );
+    replay.join(session.player_metadata()[0].clone()).unwrap();
+    for frame in &frames {
+        let (timestamp, action) = unstamp_action(frame).unwrap();
+        replay.dispatch_at(&action, timestamp).unwrap();
+    }
+
+    assert_eq!(replay.player_count(), 2);
+    assert_eq!(replay.state_hash(), session.state_hash());
+    assert_eq!(replay.system_action_hash(), session.system_action_hash());
+}
+
+#[test]
+fn program_trace_follows_the_session_state_hash() {
+    let mut session = TurboSession::<RoundGame>::new(RoundConfig::default());
+    session.join_random().unwrap();
+
+    let mut state_hashes = vec![session.state_hash()];
+    session.dispatch(&[0, 0]).unwrap();
+    state_hashes.push(session.state_hash());
+    session.join_random().unwrap();
+    state_hashes.push(session.state_hash());
+    session.leave(0).unwrap();
+    state_hashes.push(session.state_hash());
+    session
+        .dispatch_system(&RoundSystemAction::EndRound)
+        .unwrap();
+    state_hashes.push(session.state_hash());
+
+    // A rejected action is traced as well, without changing the hash
+    let mut actions = session.actions().clone();
+    actions.extend(stamp_action(&[1, 1], u64::MAX).unwrap());
+    state_hashes.push(session.state_hash());
+
+    let trace = trace_turbo_program::<RoundGame>(
+        session.server_metadata(),
+        session.config(),
+        session.initial_player_metadata().to_vec(),
+        None,
+        &encode_actions(&actions),
+    );
+    assert_eq!(trace, state_hashes);
+}
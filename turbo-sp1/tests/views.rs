@@ -0,0 +1,200 @@
+use alloy_sol_types::sol;
+use serde::{Deserialize, Serialize};
+use turbo_program::{
+    context::TurboActionContext,
+    crypto::{fnv::FnvHasher, keccak::keccak256, signature::PlayerPublicKey},
+    error::GameError,
+    framing::encode_actions,
+    game::{NoSystemAction, TurboGame, TurboPlayerView},
+    metadata::{seed_to_bytes, PlayerMetadata},
+    program::run_turbo_program,
+    traits::TurboAction,
+    view::{decrypt_view, PlayerSecretKey},
+};
+use turbo_sp1::session::{random_player_seed, TurboSession};
+
+sol! {
+    #[derive(Serialize, Deserialize, Debug, Default)]
+    struct TableState {
+        uint32 draws;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+struct Hands {
+    hands: Vec<Vec<u32>>,
+}
+
+/// A player sees the table and its own hand only.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SeatView {
+    draws: u32,
+    hand: Vec<u32>,
+}
+
+impl TurboPlayerView<TableState, Hands> for SeatView {
+    fn for_player(public_state: &TableState, private_state: &Hands, player_index: usize) -> Self {
+        SeatView {
+            draws: public_state.draws,
+            hand: private_state.hands[player_index].clone(),
+        }
+    }
+}
+
+#[derive(TurboAction, Debug, PartialEq)]
+enum CardAction {
+    Deal,
+}
+
+struct CardGame;
+
+impl TurboGame for CardGame {
+    type PublicState = TableState;
+    type PrivateState = Hands;
+    type Output = TableState;
+    type PlayerView = SeatView;
+    type Action = CardAction;
+    type SystemAction = NoSystemAction;
+    type Config = ();
+    type ActionHasher = FnvHasher;
+
+    const ENCRYPTED_VIEWS: bool = true;
+
+    fn init(_config: &(), players: &[PlayerMetadata]) -> (TableState, Hands) {
+        let hands = Hands {
+            hands: vec![Vec::new(); players.len()],
+        };
+        (TableState::default(), hands)
+    }
+
+    fn reduce(
+        public_state: &mut TableState,
+        private_state: &mut Hands,
+        _action: &CardAction,
+        context: &mut TurboActionContext,
+    ) -> Result<(), GameError> {
+        public_state.draws += 1;
+        for hand in &mut private_state.hands {
+            hand.push(context.rand_range(0..52));
+        }
+        Ok(())
+    }
+}
+
+const ED25519_SECRET: [u8; 32] = [7; 32];
+const SECP256K1_SECRET: [u8; 32] = [9; 32];
+
+fn card_session() -> TurboSession<CardGame> {
+    let ed25519 = ed25519_dalek::SigningKey::from_bytes(&ED25519_SECRET);
+    let secp256k1 = k256::ecdsa::SigningKey::from_slice(&SECP256K1_SECRET).unwrap();
+    let public_keys = [
+        Some(PlayerPublicKey::Ed25519(ed25519.verifying_key().to_bytes())),
+        Some(PlayerPublicKey::Secp256k1(
+            secp256k1
+                .verifying_key()
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec(),
+        )),
+        None,
+    ];
+
+    let mut session = TurboSession::<CardGame>::new(());
+    for public_key in public_keys {
+        session
+            .join(PlayerMetadata {
+                random_seed: random_player_seed(),
+                public_key,
+            })
+            .unwrap();
+    }
+    // The player without a key deals, keyed players would have to sign
+    session.dispatch(&[2, 0]).unwrap();
+    session.dispatch(&[2, 0]).unwrap();
+    session
+}
+
+#[test]
+fn players_are_served_their_own_view() {
+    let session = card_session();
+
+    for player_idx in 0..3 {
+        let view: SeatView = serde_json::from_value(
+            session.serialize_json(player_idx).unwrap()["public_state"].clone(),
+        )
+        .unwrap();
+        assert_eq!(view.draws, 2);
+        assert_eq!(view.hand, session.private_state().hands[player_idx]);
+    }
+}
+
+#[test]
+fn views_are_encrypted_to_each_player() {
+    let session = card_session();
+    let views = session.encrypted_player_views().unwrap();
+    assert_eq!(views.len(), 3);
+    assert!(views[2].is_empty());
+
+    let ed25519 = PlayerSecretKey::Ed25519(ED25519_SECRET);
+    let secp256k1 = PlayerSecretKey::Secp256k1(SECP256K1_SECRET);
+    for (player_idx, secret_key) in [(0, &ed25519), (1, &secp256k1)] {
+        let plaintext = decrypt_view(secret_key, &views[player_idx]).unwrap();
+        let view: SeatView = serde_json::from_slice(&plaintext).unwrap();
+        assert_eq!(view, session.player_view(player_idx).unwrap());
+    }
+
+    // Nobody else can read a view, and tampering is detected
+    assert_eq!(decrypt_view(&secp256k1, &views[0]), None);
+    assert_eq!(decrypt_view(&ed25519, &views[1]), None);
+    let mut tampered = views[0].clone();
+    tampered[40] ^= 1;
+    assert_eq!(decrypt_view(&ed25519, &tampered), None);
+
+    // The proof of the same state commits the same bytes
+    let public_values = run_turbo_program::<CardGame>(
+        session.server_metadata(),
+        &(),
+        session.initial_player_metadata().to_vec(),
+        None,
+        &encode_actions(session.actions()),
+        session.view_secret(),
+    );
+    let committed = public_values
+        .playerViews
+        .iter()
+        .map(|view| view.to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(committed, views);
+}
+
+// Decrypt with a guess of the ephemeral secret instead of the player's key: the ECDH of the guess
+// with the player's public key is the shared secret if the guess is right
+fn decrypt_with_ephemeral_seed(seed: &[u8; 32], view: &[u8]) -> Option<Vec<u8>> {
+    let public_key = ed25519_dalek::SigningKey::from_bytes(&ED25519_SECRET)
+        .verifying_key()
+        .to_bytes();
+    decrypt_view(
+        &PlayerSecretKey::Ed25519(*seed),
+        &[&public_key[..], &view[32..]].concat(),
+    )
+}
+
+fn ephemeral_seed_guess(secret: &[u8], state_hash: &[u8; 32]) -> [u8; 32] {
+    keccak256(&[b"turbo:view", secret, state_hash, &0u32.to_be_bytes()].concat())
+}
+
+#[test]
+fn revealed_seed_does_not_decrypt_the_views() {
+    let mut session = card_session();
+    let views = session.encrypted_player_views().unwrap();
+    let state_hash = session.state_hash();
+    let reveal = session.reveal_server_seed();
+
+    // The server seed and the public values are not enough to rebuild the ephemeral key
+    let guess = ephemeral_seed_guess(&seed_to_bytes(&reveal.seed), &state_hash);
+    assert_eq!(decrypt_with_ephemeral_seed(&guess, &views[0]), None);
+
+    // The view secret the server keeps is
+    let view_secret = ephemeral_seed_guess(session.view_secret(), &state_hash);
+    assert!(decrypt_with_ephemeral_seed(&view_secret, &views[0]).is_some());
+}